@@ -0,0 +1,127 @@
+use std::borrow::Cow;
+
+/// Derives and attributes added to the generated structs, for all tables or a single one.
+///
+/// A single table is named as `table`, in any schema, or as `schema.table`.
+///
+/// Derives and attributes apply to both the struct of a table and its `New` struct, column
+/// attributes to the field of the column in either. Derives for all tables are also added
+/// to the enums, composite types and domain newtypes the columns use, so that traits like
+/// `serde::Serialize` can be derived on structs with such columns.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StructAttributes {
+    derives: Vec<(Scope, Cow<'static, str>)>,
+    attributes: Vec<(Scope, Cow<'static, str>)>,
+    columns: Vec<(Scope, Cow<'static, str>, Cow<'static, str>)>,
+}
+
+/// The table something applies to, `None` for all tables.
+type Scope = Option<Cow<'static, str>>;
+
+impl StructAttributes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derive `derive`, like `Debug` or `serde::Serialize`, on the structs of `table`, or
+    /// of all tables if `None`.
+    pub fn add_derive(
+        &mut self,
+        table: Option<Cow<'static, str>>,
+        derive: Cow<'static, str>,
+    ) -> &mut Self {
+        self.derives.push((table, derive));
+        self
+    }
+
+    /// Add `attribute`, like `#[serde(rename_all = "camelCase")]`, to the structs of
+    /// `table`, or of all tables if `None`.
+    pub fn add_attribute(
+        &mut self,
+        table: Option<Cow<'static, str>>,
+        attribute: Cow<'static, str>,
+    ) -> &mut Self {
+        self.attributes.push((table, attribute));
+        self
+    }
+
+    /// Add `attribute`, like `#[serde(skip)]`, to the field of `column` of `table`, or of
+    /// every table with such a column if `None`.
+    pub fn add_column_attribute(
+        &mut self,
+        table: Option<Cow<'static, str>>,
+        column: Cow<'static, str>,
+        attribute: Cow<'static, str>,
+    ) -> &mut Self {
+        self.columns.push((table, column, attribute));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.derives.is_empty() && self.attributes.is_empty() && self.columns.is_empty()
+    }
+
+    /// The derives of the structs of `table` in `schema`, without duplicates.
+    pub fn derives<'a>(&'a self, schema: &str, table: &str) -> Vec<&'a str> {
+        let mut derives = Vec::new();
+        for (_, derive) in self
+            .derives
+            .iter()
+            .filter(|(t, _)| applies(t, schema, table))
+        {
+            if !derives.contains(&derive.as_ref()) {
+                derives.push(derive.as_ref());
+            }
+        }
+        derives
+    }
+
+    /// The derives for all tables, which user-defined types get as well.
+    pub fn global_derives(&self) -> Vec<&str> {
+        self.derives
+            .iter()
+            .filter(|(table, _)| table.is_none())
+            .map(|(_, derive)| derive.as_ref())
+            .collect()
+    }
+
+    /// The attributes of the structs of `table` in `schema`.
+    pub fn attributes<'a>(
+        &'a self,
+        schema: &'a str,
+        table: &'a str,
+    ) -> impl Iterator<Item = &'a str> {
+        self.attributes
+            .iter()
+            .filter(move |(t, _)| applies(t, schema, table))
+            .map(|(_, attribute)| attribute.as_ref())
+    }
+
+    /// The attributes of the field of `column` of `table` in `schema`.
+    pub fn column_attributes<'a>(
+        &'a self,
+        schema: &'a str,
+        table: &'a str,
+        column: &'a str,
+    ) -> impl Iterator<Item = &'a str> {
+        self.columns
+            .iter()
+            .filter(move |(t, c, _)| applies(t, schema, table) && c == column)
+            .map(|(_, _, attribute)| attribute.as_ref())
+    }
+}
+
+/// Whether something scoped to `scope` applies to `table` in `schema`.
+fn applies(scope: &Scope, schema: &str, table: &str) -> bool {
+    scope
+        .as_deref()
+        .map_or(true, |scope| table_matches(scope, schema, table))
+}
+
+/// Whether `key`, a `table` in any schema or a `schema.table`, names `table` in `schema`.
+pub(crate) fn table_matches(key: &str, schema: &str, table: &str) -> bool {
+    match key.split_once('.') {
+        Some((key_schema, key_table)) => key_schema == schema && key_table == table,
+        None => key == table,
+    }
+}
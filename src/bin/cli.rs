@@ -1,14 +1,72 @@
 use clap::Parser;
+use indexmap::IndexMap;
 use instant_models::*;
 use postgres::{Config, NoTls}; // Client
+use std::borrow::Cow;
 
 /// Generate Rust code from postgres table.
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     /// Name of the table to generate
-    #[clap(short, long, value_parser)]
-    table_name: String,
+    #[clap(short, long, value_parser, required_unless_present = "output_dir")]
+    table_name: Option<String>,
+
+    /// Schema containing the table, can be repeated with --output-dir
+    #[clap(long, value_parser, default_value = "public")]
+    schema: Vec<String>,
+
+    /// Generate every table in the schemas, writing one module per table into this directory
+    #[clap(long, value_parser, conflicts_with = "table_name")]
+    output_dir: Option<std::path::PathBuf>,
+
+    /// Only generate tables matching this glob (`table` or `schema.table`), can be repeated
+    #[clap(long, value_parser, requires = "output_dir")]
+    include: Vec<String>,
+
+    /// Skip tables matching this glob (`table` or `schema.table`), can be repeated
+    #[clap(long, value_parser, requires = "output_dir")]
+    exclude: Vec<String>,
+
+    /// File with `[schema.]table.column = rust::Type` or `pg_type = rust::Type` lines overriding the builtin type mapping
+    #[clap(long, value_parser)]
+    type_overrides: Option<std::path::PathBuf>,
+
+    /// Generate domain columns as their base type or as a newtype: base-type or newtype
+    #[clap(long, value_parser, default_value = "base-type")]
+    domain_mode: DomainMode,
+
+    /// Crate used for date and time columns: chrono, time or jiff
+    #[clap(long, value_parser, default_value = "chrono")]
+    time_crate: TimeCrate,
+
+    /// Client library of the generated code: postgres, tokio-postgres, sqlx or diesel
+    #[clap(long, value_parser, default_value = "postgres")]
+    backend: Backend,
+
+    /// Derive this on the generated structs, as `Trait` or `[schema.]table=Trait` for a single table, can be repeated
+    #[clap(long, value_parser)]
+    derive: Vec<String>,
+
+    /// Add this attribute to the generated structs, as `#[...]` or `[schema.]table=#[...]` for a single table, can be repeated
+    #[clap(long, value_parser)]
+    attribute: Vec<String>,
+
+    /// Add an attribute to the field of a column, as `column=#[...]` or `[schema.]table.column=#[...]`, can be repeated
+    #[clap(long, value_parser)]
+    column_attribute: Vec<String>,
+
+    /// Columns `upsert` detects conflicts on, as `col,col` or `[schema.]table=col,col` for a single table, can be repeated
+    #[clap(long, value_parser)]
+    on_conflict: Vec<String>,
+
+    /// Columns `upsert` updates on conflict, as `col,col` or `[schema.]table=col,col`, empty (`table=`) to do nothing, can be repeated
+    #[clap(long, value_parser)]
+    on_conflict_update: Vec<String>,
+
+    /// Introspect the table through pg_catalog instead of information_schema
+    #[clap(long)]
+    pg_catalog: bool,
 
     /// Postgres username
     #[clap(long, value_parser, default_value = "postgres")]
@@ -31,7 +89,7 @@ struct Args {
     pg_dbname: String,
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = Args::parse();
     let client = &mut Config::new()
         .user(&args.pg_username)
@@ -39,10 +97,136 @@ fn main() {
         .host(&args.pg_host)
         .port(args.pg_port)
         .dbname(&args.pg_dbname)
-        .connect(NoTls)
-        .unwrap();
-    let struct_bldr = StructBuilder::new_from_conn(client, &args.table_name).unwrap();
-    println!("{}", struct_bldr.build_type());
-    println!("\n{}", struct_bldr.build_new_type());
-    println!("\n{}", struct_bldr.build_type_methods());
+        .connect(NoTls)?;
+    let type_overrides = match &args.type_overrides {
+        Some(path) => std::fs::read_to_string(path)?.parse()?,
+        None => TypeOverrides::new(),
+    };
+
+    let mut attributes = StructAttributes::new();
+    for derive in &args.derive {
+        let (table, derive) = scoped(derive, derive.split_once('='));
+        attributes.add_derive(table, derive);
+    }
+    for attribute in &args.attribute {
+        let split = match attribute.starts_with('#') {
+            true => None,
+            false => attribute.split_once('='),
+        };
+        let (table, attribute) = scoped(attribute, split);
+        attributes.add_attribute(table, attribute);
+    }
+    for attribute in &args.column_attribute {
+        let (column, attribute) = attribute
+            .split_once('=')
+            .ok_or_else(|| format!("expected `column=#[...]`, got `{attribute}`"))?;
+        let (table, column) = scoped(column, column.rsplit_once('.'));
+        attributes.add_column_attribute(table, column, attribute.trim().to_string().into());
+    }
+
+    let mut on_conflict = IndexMap::<_, OnConflict>::new();
+    for target in &args.on_conflict {
+        let (table, columns) = scoped(target, target.split_once('='));
+        on_conflict
+            .entry(table)
+            .or_default()
+            .set_target(column_list(&columns));
+    }
+    for update in &args.on_conflict_update {
+        let (table, columns) = scoped(update, update.split_once('='));
+        let columns = column_list(&columns);
+        on_conflict
+            .entry(table)
+            .or_default()
+            .set_do_nothing(columns.is_empty())
+            .set_update(columns);
+    }
+
+    if let Some(output_dir) = &args.output_dir {
+        let mut database = DatabaseBuilder::new();
+        database
+            .set_schemas(args.schema.into_iter().map(Into::into).collect())
+            .set_type_overrides(type_overrides)
+            .set_time_crate(args.time_crate)
+            .set_domain_mode(args.domain_mode)
+            .set_backend(args.backend)
+            .set_attributes(attributes);
+        for (table, on_conflict) in on_conflict {
+            database.set_on_conflict(table, on_conflict);
+        }
+        for pattern in args.include {
+            database.add_include(pattern.into());
+        }
+        for pattern in args.exclude {
+            database.add_exclude(pattern.into());
+        }
+        if args.pg_catalog {
+            database.introspect_catalog(client)?;
+        } else {
+            database.introspect(client)?;
+        }
+        database.write_modules(output_dir)?;
+        return Ok(());
+    }
+
+    let [schema] =
+        <[String; 1]>::try_from(args.schema).map_err(|_| "--table-name takes a single --schema")?;
+    let table_name = args.table_name.unwrap_or_default();
+    let on_conflict = [
+        Some(format!("{schema}.{table_name}")),
+        Some(table_name.clone()),
+        None,
+    ]
+    .into_iter()
+    .find_map(|table| on_conflict.swap_remove(&table.map(Into::into)))
+    .unwrap_or_default();
+    let mut struct_bldr = StructBuilder::new(table_name.into());
+    struct_bldr
+        .set_schema(schema.into())
+        .set_type_overrides(type_overrides)
+        .set_time_crate(args.time_crate)
+        .set_domain_mode(args.domain_mode)
+        .set_backend(args.backend)
+        .set_attributes(attributes)
+        .set_on_conflict(on_conflict);
+    if args.pg_catalog {
+        struct_bldr.introspect_catalog(client)?;
+    } else {
+        struct_bldr.introspect(client)?;
+    }
+    let user_types = struct_bldr.build_user_types()?;
+    if !user_types.is_empty() {
+        println!("{}", user_types);
+    }
+    if args.backend == Backend::Diesel {
+        println!("{}", struct_bldr.build_diesel_schema()?);
+    }
+    println!("{}", struct_bldr.build_type()?);
+    println!("\n{}", struct_bldr.build_new_type()?);
+    println!("\n{}", struct_bldr.build_type_methods()?);
+    Ok(())
+}
+
+/// The columns of a comma-separated list.
+fn column_list(list: &str) -> Vec<Cow<'static, str>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(|column| column.to_string().into())
+        .collect()
+}
+
+/// Split `value` into the table it is scoped to and the rest, given how it splits at the
+/// separator, if it does.
+fn scoped(
+    value: &str,
+    split: Option<(&str, &str)>,
+) -> (Option<Cow<'static, str>>, Cow<'static, str>) {
+    match split {
+        Some((table, rest)) => (
+            Some(table.trim().to_string().into()),
+            rest.trim().to_string().into(),
+        ),
+        None => (None, value.trim().to_string().into()),
+    }
 }
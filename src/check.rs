@@ -0,0 +1,261 @@
+use crate::{Column, DomainMode, Type};
+use postgres_types::Type as PgType;
+
+/// A part of a CHECK constraint simple enough to be validated in Rust.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Check {
+    pub(crate) column: String,
+    rule: Rule,
+}
+
+#[derive(Debug, PartialEq)]
+enum Rule {
+    /// `length(column) <op> n`
+    Length { op: Op, value: u64 },
+    /// `column <op> literal`
+    Compare { op: Op, value: String },
+    /// `column IN (literal, ...)`
+    OneOf { values: Vec<Literal> },
+}
+
+#[derive(Debug, PartialEq)]
+enum Literal {
+    Number(String),
+    String(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    const SQL: [(&'static str, Op); 6] = [
+        (" <= ", Op::Le),
+        (" >= ", Op::Ge),
+        (" <> ", Op::Ne),
+        (" < ", Op::Lt),
+        (" > ", Op::Gt),
+        (" = ", Op::Eq),
+    ];
+
+    /// The Rust operator for values that violate the comparison.
+    fn violated_by(self) -> &'static str {
+        match self {
+            Op::Lt => ">=",
+            Op::Le => ">",
+            Op::Gt => "<=",
+            Op::Ge => "<",
+            Op::Eq => "!=",
+            Op::Ne => "==",
+        }
+    }
+}
+
+/// The expression of a CHECK constraint definition returned by `pg_get_constraintdef()`.
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+pub(crate) fn expression(definition: &str) -> &str {
+    let expr = definition.strip_prefix("CHECK ").unwrap_or(definition);
+    let end = closing_paren(expr).map_or(expr.len(), |i| i + 1);
+    strip_parens(&expr[..end])
+}
+
+/// Split a CHECK constraint expression, as returned by `pg_get_constraintdef()`, into the
+/// checks it is made of. Returns `None` if any part of it is not understood.
+pub(crate) fn parse(expression: &str) -> Option<Vec<Check>> {
+    split(strip_parens(expression), " AND ")
+        .into_iter()
+        .map(|part| parse_check(strip_parens(part)))
+        .collect()
+}
+
+fn parse_check(expr: &str) -> Option<Check> {
+    if let [lhs, rhs] = split(expr, " = ANY ")[..] {
+        let array = strip_parens(strip_cast(strip_parens(rhs)));
+        let elements = array.strip_prefix("ARRAY[")?.strip_suffix(']')?;
+        return Some(Check {
+            column: column(lhs)?,
+            rule: Rule::OneOf {
+                values: split(elements, ", ")
+                    .into_iter()
+                    .map(literal)
+                    .collect::<Option<_>>()?,
+            },
+        });
+    }
+
+    let (lhs, op, rhs) = Op::SQL
+        .iter()
+        .find_map(|(sql, op)| match split(expr, sql)[..] {
+            [lhs, rhs] => Some((strip_parens(lhs), *op, rhs)),
+            _ => None,
+        })?;
+    let length = ["length(", "char_length("]
+        .iter()
+        .find_map(|f| lhs.strip_prefix(f)?.strip_suffix(')'));
+    match (length, literal(rhs)?) {
+        (Some(arg), Literal::Number(n)) => Some(Check {
+            column: column(arg)?,
+            rule: Rule::Length {
+                op,
+                value: n.parse().ok()?,
+            },
+        }),
+        (None, Literal::Number(value)) => Some(Check {
+            column: column(lhs)?,
+            rule: Rule::Compare { op, value },
+        }),
+        _ => None,
+    }
+}
+
+/// The name of a column, possibly cast to text.
+fn column(expr: &str) -> Option<String> {
+    let expr = strip_parens(strip_cast(strip_parens(expr)));
+    if let Some(quoted) = expr.strip_prefix('"').and_then(|e| e.strip_suffix('"')) {
+        return Some(quoted.replace("\"\"", "\""));
+    }
+    match expr.chars().all(|c| c.is_alphanumeric() || c == '_') && !expr.is_empty() {
+        true => Some(expr.to_string()),
+        false => None,
+    }
+}
+
+fn literal(expr: &str) -> Option<Literal> {
+    let (value, cast) = match split(strip_parens(expr), "::")[..] {
+        [value] => (value, None),
+        [value, cast] => (strip_parens(value), Some(cast)),
+        _ => return None,
+    };
+    let numeric = matches!(
+        cast,
+        Some("smallint" | "integer" | "bigint" | "numeric" | "real" | "double precision")
+    );
+    match value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+        // Negative numbers are written as `'-1'::integer`.
+        Some(s) if numeric => is_number(s).then(|| Literal::Number(s.to_string())),
+        Some(s) => Some(Literal::String(s.replace("''", "'"))),
+        None => is_number(value).then(|| Literal::Number(value.to_string())),
+    }
+}
+
+/// Whether `value` is a finite number, rather than a column like `nan` or a special value
+/// like `'Infinity'` that also parse as `f64`.
+fn is_number(value: &str) -> bool {
+    value
+        .strip_prefix('-')
+        .unwrap_or(value)
+        .starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && value.parse::<f64>().is_ok()
+}
+
+/// Remove a trailing `::type` cast.
+fn strip_cast(expr: &str) -> &str {
+    match split(expr, "::")[..] {
+        [value, _] => value,
+        _ => expr,
+    }
+}
+
+/// Remove any parentheses wrapping all of `expr`.
+fn strip_parens(mut expr: &str) -> &str {
+    while expr.starts_with('(') && closing_paren(expr) == Some(expr.len() - 1) {
+        expr = &expr[1..expr.len() - 1];
+    }
+    expr
+}
+
+/// The index of the parenthesis closing the one `expr` starts with.
+fn closing_paren(expr: &str) -> Option<usize> {
+    let (mut depth, mut quoted) = (0, false);
+    for (i, c) in expr.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split `expr` on `sep` where it occurs outside of parentheses, brackets and string literals.
+fn split<'a>(expr: &'a str, sep: &str) -> Vec<&'a str> {
+    let mut parts = vec![];
+    let (mut depth, mut quoted, mut start) = (0i32, false, 0);
+    for (i, c) in expr.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' | '[' if !quoted => depth += 1,
+            ')' | ']' if !quoted => depth -= 1,
+            _ if !quoted && depth == 0 && i >= start && expr[i..].starts_with(sep) => {
+                parts.push(&expr[start..i]);
+                start = i + sep.len();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&expr[start..]);
+    parts
+}
+
+impl Check {
+    /// A Rust condition on `value` that holds if the check fails, given the column's type.
+    pub(crate) fn violation(&self, col: &Column, value: &str) -> Option<String> {
+        let base = base_type(&col.r#type)?;
+        let is_text = *base == PgType::TEXT;
+        // Literals must fit the Rust type of the column.
+        let number = |n: &str| match base {
+            _ if *base == PgType::INT2 => n.parse::<i16>().ok().map(|n| n.to_string()),
+            _ if *base == PgType::INT4 => n.parse::<i32>().ok().map(|n| n.to_string()),
+            _ if *base == PgType::INT8 => n.parse::<i64>().ok().map(|n| n.to_string()),
+            _ if [PgType::FLOAT4, PgType::FLOAT8].contains(base) => {
+                n.parse::<f64>().ok().map(|n| format!("{n:?}"))
+            }
+            _ => None,
+        };
+        match &self.rule {
+            Rule::Length { op, value: n } if is_text => {
+                Some(format!("{value}.chars().count() {} {n}", op.violated_by()))
+            }
+            Rule::Compare { op, value: n } => {
+                Some(format!("{value} {} {}", op.violated_by(), number(n)?))
+            }
+            Rule::OneOf { values } => {
+                let values = values
+                    .iter()
+                    .map(|v| match v {
+                        Literal::String(s) if is_text => Some(format!("{s:?}")),
+                        Literal::Number(n) => number(n),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("![{}].contains(&{value})", values.join(", ")))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The builtin type of a column as it appears in the `New` struct.
+fn base_type(r#type: &Type) -> Option<&PgType> {
+    match r#type {
+        Type::Builtin { inner } => Some(inner),
+        Type::Domain {
+            inner,
+            mode: DomainMode::BaseType,
+            ..
+        } => base_type(inner),
+        _ => None,
+    }
+}
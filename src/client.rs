@@ -0,0 +1,92 @@
+use crate::Error;
+use std::future::Future;
+use std::pin::Pin;
+use tokio_postgres::types::ToSql;
+use tokio_postgres::Row;
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A connection to run the introspection queries on.
+///
+/// Introspection is written once against this trait: the blocking client's futures are
+/// always ready, so [`block_on()`] can drive them to completion without a runtime.
+pub(crate) trait Client: Send {
+    fn query<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Vec<Row>, Error>>;
+
+    fn query_one<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Row, Error>>;
+}
+
+#[cfg(feature = "postgres")]
+pub(crate) struct Blocking<'c>(pub(crate) &'c mut postgres::Client);
+
+#[cfg(feature = "postgres")]
+impl Client for Blocking<'_> {
+    fn query<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Vec<Row>, Error>> {
+        Box::pin(std::future::ready(
+            self.0.query(sql, params).map_err(Error::from),
+        ))
+    }
+
+    fn query_one<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Row, Error>> {
+        Box::pin(std::future::ready(
+            self.0.query_one(sql, params).map_err(Error::from),
+        ))
+    }
+}
+
+#[cfg(feature = "tokio-postgres")]
+pub(crate) struct Async<'c, C>(pub(crate) &'c C);
+
+#[cfg(feature = "tokio-postgres")]
+impl<C: tokio_postgres::GenericClient + Sync> Client for Async<'_, C> {
+    fn query<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Vec<Row>, Error>> {
+        Box::pin(async move { Ok(self.0.query(sql, params).await?) })
+    }
+
+    fn query_one<'a>(
+        &'a mut self,
+        sql: &'a str,
+        params: &'a [&'a (dyn ToSql + Sync)],
+    ) -> BoxFuture<'a, Result<Row, Error>> {
+        Box::pin(async move { Ok(self.0.query_one(sql, params).await?) })
+    }
+}
+
+/// Run a future of the blocking client, which never has to wait.
+#[cfg(feature = "postgres")]
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct NoopWaker;
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut future = std::pin::pin!(future);
+    match future.as_mut().poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => unreachable!("blocking queries are always ready"),
+    }
+}
@@ -2,10 +2,29 @@ use crate::{Type, TypeAsRef};
 use heck::AsSnakeCase;
 use std::borrow::Cow;
 
+/// A foreign key a column is part of.
 #[derive(Debug, PartialEq)]
 pub struct ForeignKey {
-    to_table: Cow<'static, str>,
-    columns: Vec<Cow<'static, str>>,
+    /// The schema of `to_table`, `public` unless set.
+    pub to_schema: Cow<'static, str>,
+    pub to_table: Cow<'static, str>,
+    /// Columns of `to_table` referenced by the foreign key, in key order.
+    pub columns: Vec<Cow<'static, str>>,
+}
+
+impl ForeignKey {
+    pub fn new(to_table: Cow<'static, str>, columns: Vec<Cow<'static, str>>) -> Self {
+        Self {
+            to_schema: "public".into(),
+            to_table,
+            columns,
+        }
+    }
+
+    pub fn set_to_schema(mut self, value: Cow<'static, str>) -> Self {
+        self.to_schema = value;
+        self
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,6 +37,21 @@ pub struct Column {
     pub unique: bool,
     pub default: Option<Cow<'static, str>>,
     pub type_def: Option<Cow<'static, str>>,
+    pub domain: Option<Cow<'static, str>>,
+    pub identity: Option<Identity>,
+    /// Whether this is a `GENERATED ALWAYS AS (...) STORED` column.
+    pub generated: bool,
+    /// The `COMMENT ON COLUMN`, generated as the field's documentation.
+    pub comment: Option<Cow<'static, str>>,
+}
+
+/// How the values of an identity column are generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Identity {
+    /// `GENERATED ALWAYS AS IDENTITY`
+    Always,
+    /// `GENERATED BY DEFAULT AS IDENTITY`
+    ByDefault,
 }
 
 impl Column {
@@ -31,6 +65,10 @@ impl Column {
             unique: false,
             default: None,
             type_def: None,
+            domain: None,
+            identity: None,
+            generated: false,
+            comment: None,
         }
     }
 
@@ -63,6 +101,46 @@ impl Column {
         self.type_def = value;
         self
     }
+
+    pub fn set_domain(mut self, value: Option<Cow<'static, str>>) -> Self {
+        self.domain = value;
+        self
+    }
+
+    pub fn set_identity(mut self, value: Option<Identity>) -> Self {
+        self.identity = value;
+        self
+    }
+
+    pub fn set_generated(mut self, value: bool) -> Self {
+        self.generated = value;
+        self
+    }
+
+    pub fn set_comment(mut self, value: Option<Cow<'static, str>>) -> Self {
+        self.comment = value;
+        self
+    }
+
+    /// Whether Postgres always generates the value, so that it can't be inserted.
+    pub fn is_generated(&self) -> bool {
+        self.generated || self.identity == Some(Identity::Always)
+    }
+
+    /// Whether Postgres can fill in the value if none is inserted.
+    pub fn has_default(&self) -> bool {
+        self.default.is_some() || self.identity == Some(Identity::ByDefault)
+    }
+
+    /// Whether Postgres numbers the values from a sequence, as for `serial` and identity
+    /// columns.
+    pub fn is_serial(&self) -> bool {
+        self.identity.is_some()
+            || self
+                .default
+                .as_deref()
+                .is_some_and(|default| default.starts_with("nextval("))
+    }
 }
 
 impl std::fmt::Display for Column {
@@ -82,14 +160,19 @@ pub struct NewValue<'a> {
 
 impl std::fmt::Display for NewValue<'_> {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        if self.val.null && self.val.default.is_some() {
-            panic!(
-                "Column `{}` is both NULL and takes a default value `{}`",
-                self.val.name,
-                self.val.default.as_ref().unwrap()
+        // `None` inserts the default, `Some(None)` inserts NULL.
+        if self.val.null && self.val.has_default() {
+            return write!(
+                fmt,
+                "{}: Option<Option<{}>>",
+                AsSnakeCase(&self.val.name),
+                TypeAsRef {
+                    lifetime: self.lifetime,
+                    val: &self.val.r#type
+                }
             );
         }
-        if self.val.null || self.val.default.is_some() {
+        if self.val.null || self.val.has_default() {
             write!(
                 fmt,
                 "{}: Option<{}>",
@@ -118,6 +201,7 @@ pub enum Constraint {
     ForeignKey {
         name: Cow<'static, str>,
         columns: Cow<'static, [Cow<'static, str>]>,
+        ref_schema: Cow<'static, str>,
         ref_table: Cow<'static, str>,
         ref_columns: Cow<'static, [Cow<'static, str>]>,
     },
@@ -125,4 +209,14 @@ pub enum Constraint {
         name: Cow<'static, str>,
         columns: Vec<Cow<'static, str>>,
     },
+    /// A `UNIQUE` constraint, or a unique index on plain columns that isn't partial.
+    Unique {
+        name: Cow<'static, str>,
+        columns: Vec<Cow<'static, str>>,
+    },
+    /// A `CHECK` constraint, with the `expression` it checks as formatted by Postgres.
+    Check {
+        name: Cow<'static, str>,
+        expression: Cow<'static, str>,
+    },
 }
@@ -0,0 +1,398 @@
+#[cfg(feature = "tokio-postgres")]
+use crate::client::Async;
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+use crate::client::Client;
+#[cfg(feature = "postgres")]
+use crate::client::{block_on, Blocking};
+use crate::{
+    Backend, DomainMode, Error, OnConflict, StructAttributes, StructBuilder, TimeCrate,
+    TypeOverrides,
+};
+use heck::AsSnakeCase;
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Generates code for all tables in one or more schemas.
+///
+/// The tables can be narrowed down with glob patterns, where `*` matches any number of
+/// characters and `?` a single one. Patterns containing a `.` are matched against
+/// `schema.table`, others against the table name.
+#[derive(Debug, PartialEq)]
+pub struct DatabaseBuilder {
+    pub schemas: Vec<Cow<'static, str>>,
+    pub include: Vec<Cow<'static, str>>,
+    pub exclude: Vec<Cow<'static, str>>,
+    pub tables: Vec<StructBuilder>,
+    pub type_overrides: TypeOverrides,
+    pub time_crate: TimeCrate,
+    pub domain_mode: DomainMode,
+    pub backend: Backend,
+    pub attributes: StructAttributes,
+    /// How the `upsert` methods of a table handle conflicts, by table or `None` for all.
+    pub on_conflict: IndexMap<Option<Cow<'static, str>>, OnConflict>,
+}
+
+impl Default for DatabaseBuilder {
+    fn default() -> Self {
+        Self {
+            schemas: vec!["public".into()],
+            include: vec![],
+            exclude: vec![],
+            tables: vec![],
+            type_overrides: TypeOverrides::default(),
+            time_crate: TimeCrate::default(),
+            domain_mode: DomainMode::default(),
+            backend: Backend::default(),
+            attributes: StructAttributes::default(),
+            on_conflict: IndexMap::new(),
+        }
+    }
+}
+
+impl DatabaseBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Introspect the tables in `schemas` rather than in `public`.
+    pub fn set_schemas(&mut self, schemas: Vec<Cow<'static, str>>) -> &mut Self {
+        self.schemas = schemas;
+        self
+    }
+
+    /// Only generate tables matching `pattern`, or any of the other included patterns.
+    pub fn add_include(&mut self, pattern: Cow<'static, str>) -> &mut Self {
+        self.include.push(pattern);
+        self
+    }
+
+    /// Skip tables matching `pattern`, even if they are included.
+    pub fn add_exclude(&mut self, pattern: Cow<'static, str>) -> &mut Self {
+        self.exclude.push(pattern);
+        self
+    }
+
+    pub fn set_type_overrides(&mut self, type_overrides: TypeOverrides) -> &mut Self {
+        self.type_overrides = type_overrides;
+        self
+    }
+
+    pub fn set_time_crate(&mut self, time_crate: TimeCrate) -> &mut Self {
+        self.time_crate = time_crate;
+        for table in &mut self.tables {
+            table.set_time_crate(time_crate);
+        }
+        self
+    }
+
+    pub fn set_domain_mode(&mut self, domain_mode: DomainMode) -> &mut Self {
+        self.domain_mode = domain_mode;
+        for table in &mut self.tables {
+            table.set_domain_mode(domain_mode);
+        }
+        self
+    }
+
+    pub fn set_backend(&mut self, backend: Backend) -> &mut Self {
+        self.backend = backend;
+        for table in &mut self.tables {
+            table.set_backend(backend);
+        }
+        self
+    }
+
+    /// Add the derives and attributes of `attributes` to the structs of every table.
+    pub fn set_attributes(&mut self, attributes: StructAttributes) -> &mut Self {
+        for table in &mut self.tables {
+            table.set_attributes(attributes.clone());
+        }
+        self.attributes = attributes;
+        self
+    }
+
+    /// Choose how the `upsert` methods of `table`, or of all tables without their own
+    /// choice if `None`, handle conflicts. `table` is a table in any schema, or
+    /// `schema.table`, which takes precedence.
+    pub fn set_on_conflict(
+        &mut self,
+        table: Option<Cow<'static, str>>,
+        on_conflict: OnConflict,
+    ) -> &mut Self {
+        self.on_conflict.insert(table, on_conflict);
+        let choices = self
+            .tables
+            .iter()
+            .map(|table| self.table_on_conflict(&table.schema, &table.name))
+            .collect::<Vec<_>>();
+        for (table, on_conflict) in self.tables.iter_mut().zip(choices) {
+            table.set_on_conflict(on_conflict);
+        }
+        self
+    }
+
+    /// How the `upsert` methods of `table` in `schema` handle conflicts.
+    fn table_on_conflict(&self, schema: &str, table: &str) -> OnConflict {
+        self.on_conflict
+            .get(&Some(Cow::Owned(format!("{schema}.{table}"))))
+            .or_else(|| self.on_conflict.get(&Some(Cow::Owned(table.to_string()))))
+            .or_else(|| self.on_conflict.get(&None))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether code should be generated for `table` in `schema`.
+    pub fn is_selected(&self, schema: &str, table: &str) -> bool {
+        let qualified = format!("{schema}.{table}");
+        let matches = |pattern: &Cow<'static, str>| match pattern.contains('.') {
+            true => glob_match(pattern, &qualified),
+            false => glob_match(pattern, table),
+        };
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+
+    /// Add all selected tables, reading `information_schema` like [`StructBuilder::introspect()`].
+    #[cfg(feature = "postgres")]
+    pub fn introspect(&mut self, client: &mut postgres::Client) -> Result<&mut Self, Error> {
+        block_on(self.read_tables(&mut Blocking(client), false))?;
+        Ok(self)
+    }
+
+    /// Add all selected tables, reading `pg_catalog` like [`StructBuilder::introspect_catalog()`].
+    #[cfg(feature = "postgres")]
+    pub fn introspect_catalog(
+        &mut self,
+        client: &mut postgres::Client,
+    ) -> Result<&mut Self, Error> {
+        block_on(self.read_tables(&mut Blocking(client), true))?;
+        Ok(self)
+    }
+
+    /// Like [`introspect()`](Self::introspect), through an async client.
+    #[cfg(feature = "tokio-postgres")]
+    pub async fn introspect_async(
+        &mut self,
+        client: &(impl tokio_postgres::GenericClient + Sync),
+    ) -> Result<&mut Self, Error> {
+        self.read_tables(&mut Async(client), false).await?;
+        Ok(self)
+    }
+
+    /// Like [`introspect_catalog()`](Self::introspect_catalog), through an async client.
+    #[cfg(feature = "tokio-postgres")]
+    pub async fn introspect_catalog_async(
+        &mut self,
+        client: &(impl tokio_postgres::GenericClient + Sync),
+    ) -> Result<&mut Self, Error> {
+        self.read_tables(&mut Async(client), true).await?;
+        Ok(self)
+    }
+
+    #[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+    async fn read_tables(&mut self, client: &mut impl Client, catalog: bool) -> Result<(), Error> {
+        let schemas = self.schemas.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+        for row in client.query("SELECT n.nspname, c.relname FROM pg_class AS c JOIN pg_namespace AS n ON n.oid = c.relnamespace WHERE n.nspname = ANY($1) AND c.relkind IN ('r', 'p') AND NOT c.relispartition ORDER BY n.nspname, c.relname;", &[&schemas]).await? {
+            let schema: String = row.get(0);
+            let name: String = row.get(1);
+            if !self.is_selected(&schema, &name) {
+                continue;
+            }
+            let on_conflict = self.table_on_conflict(&schema, &name);
+            let mut table = StructBuilder::new(name.into());
+            table
+                .set_schema(schema.into())
+                .set_type_overrides(self.type_overrides.clone())
+                .set_time_crate(self.time_crate)
+                .set_domain_mode(self.domain_mode)
+                .set_backend(self.backend)
+                .set_attributes(self.attributes.clone())
+                .set_on_conflict(on_conflict);
+            match catalog {
+                true => table.read_pg_catalog(client).await?,
+                false => table.read_information_schema(client).await?,
+            }
+            self.tables.push(table);
+        }
+        Ok(())
+    }
+
+    /// The source of each generated module by file name: a `mod.rs` declaring the others,
+    /// a `types.rs` with the user-defined types shared by all tables, and one module per
+    /// table. Tables with the same name in different schemas are prefixed with their schema;
+    /// user-defined types with the same Rust name are an [`Error::AmbiguousType`].
+    ///
+    /// With [`Backend::Diesel`], a `schema.rs` declares the `table!` of every table, which
+    /// requires table names to be unique across schemas.
+    pub fn build_modules(&self) -> Result<IndexMap<String, String>, Error> {
+        let mut shared_types = IndexMap::new();
+        for table in &self.tables {
+            table.collect_user_types(&mut shared_types)?;
+        }
+
+        let mut modules = IndexMap::new();
+        let mut mod_rs = String::new();
+        let diesel = self.backend == Backend::Diesel;
+        let mut types = String::new();
+        if diesel {
+            let (schema, sql_types) = self.build_diesel_schema()?;
+            mod_rs.push_str("pub mod schema;\n");
+            modules.insert("schema.rs".to_string(), schema);
+            if sql_types {
+                types.push_str("use super::schema::sql_types;\n\n");
+            }
+        }
+        if !shared_types.is_empty() {
+            mod_rs.push_str("pub mod types;\n");
+        }
+        for table in &self.tables {
+            let module = self.module_name(table);
+            let mut source = String::new();
+            if diesel {
+                source.push_str(&format!(
+                    "use super::schema::{};\n",
+                    AsSnakeCase(&table.name)
+                ));
+            }
+            if !table.build_user_types()?.is_empty() {
+                source.push_str("use super::types::*;\n");
+            }
+            if !source.is_empty() {
+                source.push('\n');
+            }
+            source.push_str(&table.build_type()?);
+            source.push_str(&table.build_new_type()?);
+            source.push_str(&table.build_type_methods()?);
+            mod_rs.push_str(&format!("pub mod {module};\n"));
+            modules.insert(format!("{module}.rs"), source);
+        }
+        if !shared_types.is_empty() {
+            types.extend(shared_types.into_values());
+            modules.insert("types.rs".to_string(), types);
+        }
+        modules.insert("mod.rs".to_string(), mod_rs);
+        modules.move_index(modules.len() - 1, 0);
+        Ok(modules)
+    }
+
+    /// The source of `schema.rs` for [`Backend::Diesel`], and whether it declares any
+    /// `sql_types`. Tables are joinable through single-column foreign keys to the primary
+    /// key of another generated table.
+    fn build_diesel_schema(&self) -> Result<(String, bool), Error> {
+        let mut sql_types = IndexMap::new();
+        let mut tables = String::new();
+        let mut joins = String::new();
+        for table in &self.tables {
+            if self.module_name(table) != AsSnakeCase(&table.name).to_string() {
+                return Err(Error::AmbiguousTable {
+                    table: table.name.to_string().into(),
+                });
+            }
+            table.collect_diesel_sql_types(&mut sql_types);
+            tables.push_str(&table.build_diesel_table()?);
+            tables.push('\n');
+            for (to_table, col) in table.diesel_joins() {
+                let Some(fk) = &col.foreign_key else {
+                    continue;
+                };
+                let Some(target) = self
+                    .tables
+                    .iter()
+                    .find(|t| t.name == to_table && t.schema == fk.to_schema)
+                else {
+                    continue;
+                };
+                let key = target.primary_key();
+                let references = fk.columns.iter();
+                if key.len() != 1 || !references.eq([&key[0].name]) {
+                    continue;
+                }
+                joins.push_str(&format!(
+                    "diesel::joinable!({} -> {} ({}));\n",
+                    AsSnakeCase(&table.name),
+                    AsSnakeCase(to_table),
+                    AsSnakeCase(&col.name)
+                ));
+            }
+        }
+
+        let mut source = String::new();
+        if !sql_types.is_empty() {
+            source.push_str(&format!(
+                "pub mod sql_types {{\n{}}}\n\n",
+                sql_types.values().cloned().collect::<Vec<_>>().join("\n")
+            ));
+        }
+        source.push_str(&tables);
+        source.push_str(&joins);
+        if self.tables.len() > 1 {
+            if !joins.is_empty() {
+                source.push('\n');
+            }
+            source.push_str(&format!(
+                "diesel::allow_tables_to_appear_in_same_query!(\n{});\n",
+                self.tables.iter().fold(String::new(), |mut acc, table| {
+                    acc.push_str(&format!("    {},\n", AsSnakeCase(&table.name)));
+                    acc
+                })
+            ));
+        }
+        Ok((source, !sql_types.is_empty()))
+    }
+
+    /// Write the modules from [`build_modules()`](Self::build_modules) into `dir`.
+    pub fn write_modules(&self, dir: &Path) -> Result<(), Error> {
+        let modules = self.build_modules()?;
+        std::fs::create_dir_all(dir)?;
+        for (file_name, source) in modules {
+            std::fs::write(dir.join(file_name), source)?;
+        }
+        Ok(())
+    }
+
+    fn module_name(&self, table: &StructBuilder) -> String {
+        let ambiguous = self
+            .tables
+            .iter()
+            .filter(|t| t.name == table.name)
+            .nth(1)
+            .is_some();
+        match ambiguous {
+            true => format!(
+                "{}_{}",
+                AsSnakeCase(&table.schema),
+                AsSnakeCase(&table.name)
+            ),
+            false => AsSnakeCase(&table.name).to_string(),
+        }
+    }
+}
+
+/// Match `text` against `pattern`, where `*` matches any characters and `?` any single one.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.as_bytes(), text.as_bytes());
+    let (mut p, mut t) = (0, 0);
+    // Where to resume after the last `*` if the rest fails to match.
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    p = star + 1;
+                    t = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
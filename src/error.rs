@@ -0,0 +1,92 @@
+use std::borrow::Cow;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("column `{column}` of `{table}` has unsupported type `{pg_type}`")]
+    UnsupportedType {
+        table: Cow<'static, str>,
+        column: Cow<'static, str>,
+        pg_type: Cow<'static, str>,
+    },
+    #[error("unsupported type `{pg_type}`")]
+    UnknownType { pg_type: Cow<'static, str> },
+    #[error("label `{label}` of enum `{pg_type}` has no Rust variant name of its own")]
+    InvalidVariant {
+        pg_type: Cow<'static, str>,
+        label: Cow<'static, str>,
+    },
+    #[error("column `{column}` of `{table}` has type `{pg_type}`, which `{backend}` has no Rust type for; use a type override")]
+    UnsupportedByBackend {
+        table: Cow<'static, str>,
+        column: Cow<'static, str>,
+        pg_type: Cow<'static, str>,
+        backend: &'static str,
+    },
+    #[error("table `{table}` has no primary key, which diesel's `table!` requires")]
+    MissingPrimaryKey { table: Cow<'static, str> },
+    #[error("table `{table}` exists in several schemas, which diesel's `table!` can't tell apart")]
+    AmbiguousTable { table: Cow<'static, str> },
+    #[error("types `{first}` and `{second}` would both be generated as `{rust_type}`")]
+    AmbiguousType {
+        first: Cow<'static, str>,
+        second: Cow<'static, str>,
+        rust_type: Cow<'static, str>,
+    },
+    #[error("columns ({columns}) of `{table}` are not an inserted primary key or unique constraint to upsert on")]
+    InvalidConflictTarget {
+        table: Cow<'static, str>,
+        columns: Cow<'static, str>,
+    },
+    #[error("column `{column}` of `{table}` is not inserted, or is in the conflict target, so it can't be updated on conflict")]
+    InvalidConflictUpdate {
+        table: Cow<'static, str>,
+        column: Cow<'static, str>,
+    },
+    #[error("`{pg_type}` columns require the `{feature}` feature")]
+    MissingFeature {
+        pg_type: Cow<'static, str>,
+        feature: &'static str,
+    },
+    #[error("constraint `{name}` of `{table}` has unknown type `{constraint_type}`")]
+    UnknownConstraint {
+        table: Cow<'static, str>,
+        name: Cow<'static, str>,
+        constraint_type: Cow<'static, str>,
+    },
+    #[error("constraint `{name}` of `{table}` refers to unknown column `{column}`")]
+    UnknownColumn {
+        table: Cow<'static, str>,
+        name: Cow<'static, str>,
+        column: Cow<'static, str>,
+    },
+    #[error("invalid value `{value}`, expected {expected}")]
+    InvalidValue {
+        value: Cow<'static, str>,
+        expected: &'static str,
+    },
+    #[error("line {line}: expected `key = rust::Type`, got `{content}`")]
+    InvalidOverride {
+        line: usize,
+        content: Cow<'static, str>,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+    #[error(transparent)]
+    Postgres(#[from] tokio_postgres::Error),
+}
+
+impl Error {
+    /// Turn an [`Error::UnknownType`], which a type reports without knowing where it is
+    /// used, into the [`Error::UnsupportedType`] of `column` of `table`.
+    pub(crate) fn in_column(self, table: &str, column: &str) -> Self {
+        match self {
+            Self::UnknownType { pg_type } => Self::UnsupportedType {
+                table: table.to_string().into(),
+                column: column.to_string().into(),
+                pg_type,
+            },
+            err => err,
+        }
+    }
+}
@@ -6,3 +6,23 @@ pub use column::*;
 
 mod types;
 pub use types::*;
+
+mod database;
+pub use database::*;
+
+mod overrides;
+pub use overrides::*;
+
+mod attributes;
+pub use attributes::*;
+
+mod on_conflict;
+pub use on_conflict::*;
+
+mod error;
+pub use error::*;
+
+mod check;
+
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+mod client;
@@ -0,0 +1,44 @@
+use std::borrow::Cow;
+
+/// What the generated `upsert` and `upsert_slice` do with rows that conflict with existing
+/// ones.
+///
+/// By default, conflicts are detected on the primary key if it is inserted, or else on the
+/// first unique constraint whose columns are, and update every other inserted column without
+/// a default, since an entry leaving it to `DEFAULT` would overwrite the existing value.
+/// Tables without such a key get no `upsert` methods, unless a target is set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OnConflict {
+    /// The columns of the primary key or unique constraint to detect conflicts on.
+    pub target: Option<Vec<Cow<'static, str>>>,
+    /// The columns to update to the values of the conflicting row.
+    pub update: Option<Vec<Cow<'static, str>>>,
+    /// Whether to leave the existing row as it is instead.
+    pub do_nothing: bool,
+}
+
+impl OnConflict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Detect conflicts on `columns`, which must be the primary key or a unique constraint.
+    pub fn set_target(&mut self, columns: Vec<Cow<'static, str>>) -> &mut Self {
+        self.target = Some(columns);
+        self
+    }
+
+    /// Only update `columns` on conflict, rather than every inserted column outside the
+    /// target without a default. A column with a default is set to it for entries leaving
+    /// it out.
+    pub fn set_update(&mut self, columns: Vec<Cow<'static, str>>) -> &mut Self {
+        self.update = Some(columns);
+        self
+    }
+
+    /// Leave conflicting rows as they are, with `ON CONFLICT DO NOTHING`.
+    pub fn set_do_nothing(&mut self, value: bool) -> &mut Self {
+        self.do_nothing = value;
+        self
+    }
+}
@@ -0,0 +1,104 @@
+use crate::attributes::table_matches;
+use crate::{Error, Type};
+use indexmap::IndexMap;
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// Rust types to use instead of the builtin mapping, consulted before it during introspection.
+///
+/// Columns are matched by `table.column` (or `composite_type.attribute`) first, then by the
+/// name of their domain and finally by the name of their Postgres type. The table may be
+/// qualified as `schema.table`, which takes precedence over the table in any schema.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeOverrides {
+    columns: IndexMap<(Cow<'static, str>, Cow<'static, str>), Cow<'static, str>>,
+    types: IndexMap<Cow<'static, str>, Cow<'static, str>>,
+}
+
+impl TypeOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `rust_type` for `column` of `table`, which is either a table in any schema or
+    /// `schema.table`.
+    pub fn add_column(
+        &mut self,
+        table: Cow<'static, str>,
+        column: Cow<'static, str>,
+        rust_type: Cow<'static, str>,
+    ) -> &mut Self {
+        self.columns.insert((table, column), rust_type);
+        self
+    }
+
+    /// Use `rust_type` for every column of the Postgres type or domain `pg_type`.
+    pub fn add_type(
+        &mut self,
+        pg_type: Cow<'static, str>,
+        rust_type: Cow<'static, str>,
+    ) -> &mut Self {
+        self.types.insert(pg_type, rust_type);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty() && self.types.is_empty()
+    }
+
+    /// The override of `column` of `table` in `schema`, preferring one for `schema.table`.
+    pub fn column(&self, schema: &str, table: &str, column: &str) -> Option<Type> {
+        let matching = |qualified: bool| {
+            self.columns.iter().find(|((t, c), _)| {
+                t.contains('.') == qualified && table_matches(t, schema, table) && c == column
+            })
+        };
+        matching(true)
+            .or_else(|| matching(false))
+            .map(|(_, path)| Type::Custom {
+                path: path.clone(),
+                replaces: None,
+            })
+    }
+
+    pub fn r#type(&self, pg_type: &str) -> Option<Type> {
+        self.types.get(pg_type).map(|path| Type::Custom {
+            path: path.clone(),
+            replaces: None,
+        })
+    }
+}
+
+/// Parse overrides from lines of `key = rust::Type`, where `key` is either `table.column`,
+/// `schema.table.column` or the name of a Postgres type or domain. Empty lines and lines
+/// starting with `#` are ignored.
+impl FromStr for TypeOverrides {
+    type Err = Error;
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        let mut overrides = Self::new();
+        for (i, line) in val.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || Error::InvalidOverride {
+                line: i + 1,
+                content: line.to_string().into(),
+            };
+            let (key, rust_type) = line.split_once('=').ok_or_else(invalid)?;
+            let (key, rust_type) = (key.trim(), rust_type.trim());
+            if key.is_empty() || rust_type.is_empty() {
+                return Err(invalid());
+            }
+            match key.rsplit_once('.') {
+                Some((table, column)) => overrides.add_column(
+                    table.to_string().into(),
+                    column.to_string().into(),
+                    rust_type.to_string().into(),
+                ),
+                None => overrides.add_type(key.to_string().into(), rust_type.to_string().into()),
+            };
+        }
+        Ok(overrides)
+    }
+}
@@ -1,22 +1,50 @@
-use crate::{Column, Constraint, NewValue, Type};
+use crate::check;
+#[cfg(feature = "tokio-postgres")]
+use crate::client::Async;
+#[cfg(feature = "postgres")]
+use crate::client::{block_on, Blocking};
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+use crate::client::{BoxFuture, Client};
+use crate::{
+    Backend, Column, Constraint, DomainMode, Error, NewValue, OnConflict, StructAttributes,
+    TimeCrate, Type, TypeAsRef, TypeOverrides,
+};
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+use crate::{ForeignKey, Identity};
 use heck::{AsSnakeCase, AsUpperCamelCase};
 use indexmap::IndexMap;
 use std::borrow::Cow;
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq)]
 pub struct StructBuilder {
     pub name: Cow<'static, str>,
+    /// The schema containing the table, `public` by default.
+    pub schema: Cow<'static, str>,
     pub columns: IndexMap<Cow<'static, str>, Column>,
     pub constraints: Vec<Constraint>,
+    pub type_overrides: TypeOverrides,
+    pub time_crate: TimeCrate,
+    pub domain_mode: DomainMode,
+    pub backend: Backend,
+    pub attributes: StructAttributes,
+    pub on_conflict: OnConflict,
 }
 
 impl Default for StructBuilder {
     fn default() -> Self {
         Self {
             name: String::new().into(),
+            schema: "public".into(),
             columns: IndexMap::new(),
             constraints: vec![],
+            type_overrides: TypeOverrides::default(),
+            time_crate: TimeCrate::default(),
+            domain_mode: DomainMode::default(),
+            backend: Backend::default(),
+            attributes: StructAttributes::default(),
+            on_conflict: OnConflict::default(),
         }
     }
 }
@@ -34,44 +62,351 @@ impl StructBuilder {
         self
     }
 
-    pub fn build_type(&self) -> String {
-        format!("{}", self)
-    }
-
-    pub fn build_new_type(&self) -> String {
-        let columns =
-            self.columns
-                .values()
-                .filter(|c| !c.primary_key)
-                .fold(String::new(), |mut acc, col| {
-                    acc.push_str(&format!(
-                        "    pub {},",
-                        NewValue {
-                            val: col,
-                            lifetime: Some("a")
-                        }
-                    ));
-                    acc.push('\n');
-                    acc
+    /// Introspect the table in `schema` rather than in `public`.
+    pub fn set_schema(&mut self, schema: Cow<'static, str>) -> &mut Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Use `type_overrides` instead of the builtin type mapping when introspecting.
+    pub fn set_type_overrides(&mut self, type_overrides: TypeOverrides) -> &mut Self {
+        self.type_overrides = type_overrides;
+        self
+    }
+
+    /// Use `time_crate` for the date and time columns of the generated code.
+    pub fn set_time_crate(&mut self, time_crate: TimeCrate) -> &mut Self {
+        self.time_crate = time_crate;
+        for col in self.columns.values_mut() {
+            col.r#type.set_time_crate(time_crate);
+        }
+        self
+    }
+
+    /// Generate columns whose type is a domain according to `domain_mode`.
+    pub fn set_domain_mode(&mut self, domain_mode: DomainMode) -> &mut Self {
+        self.domain_mode = domain_mode;
+        for col in self.columns.values_mut() {
+            col.r#type.set_domain_mode(domain_mode);
+        }
+        self
+    }
+
+    /// Generate methods for the client library `backend`.
+    pub fn set_backend(&mut self, backend: Backend) -> &mut Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Add the derives and attributes of `attributes` to the generated structs.
+    pub fn set_attributes(&mut self, attributes: StructAttributes) -> &mut Self {
+        self.attributes = attributes;
+        self
+    }
+
+    /// Choose how `upsert` and `upsert_slice` handle conflicts.
+    pub fn set_on_conflict(&mut self, on_conflict: OnConflict) -> &mut Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    /// Check that code can be generated for all columns.
+    pub fn check(&self) -> Result<(), Error> {
+        for col in self.columns.values() {
+            col.r#type
+                .check()
+                .map_err(|err| err.in_column(&self.name, &col.name))?;
+            if let Some(r#type) = col.r#type.unsupported_by(self.backend) {
+                return Err(Error::UnsupportedByBackend {
+                    table: self.name.to_string().into(),
+                    column: col.name.to_string().into(),
+                    pg_type: r#type
+                        .pg_name()
+                        .unwrap_or_else(|| r#type.to_string())
+                        .into(),
+                    backend: self.backend.crate_path(),
                 });
+            }
+        }
+        self.conflict_columns()?;
+        Ok(())
+    }
 
-        format!(
-            r#"pub struct {}New<'a> {{
+    /// The columns `upsert` detects conflicts on and the columns it updates then, `None`
+    /// if there is no key to detect them on.
+    fn conflict_columns(&self) -> Result<Option<ConflictColumns<'_>>, Error> {
+        let inserted = |key: &[&Column]| key.iter().all(|col| is_inserted(col));
+        let target = match &self.on_conflict.target {
+            Some(names) => {
+                let invalid = || Error::InvalidConflictTarget {
+                    table: self.name.to_string().into(),
+                    columns: names.join(", ").into(),
+                };
+                let target = names
+                    .iter()
+                    .map(|name| self.columns.get(name).ok_or_else(invalid))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let same_columns = |key: &[&Column]| {
+                    key.len() == target.len() && key.iter().all(|col| target.contains(col))
+                };
+                let primary_key = self.primary_key();
+                if !inserted(&target)
+                    || !(same_columns(&primary_key)
+                        || self.unique_keys().iter().any(|key| same_columns(key)))
+                {
+                    return Err(invalid());
+                }
+                target
+            }
+            None => {
+                let primary_key = self.primary_key();
+                match !primary_key.is_empty() && inserted(&primary_key) {
+                    true => primary_key,
+                    false => match self.unique_keys().into_iter().find(|key| inserted(key)) {
+                        Some(key) => key,
+                        None => return Ok(None),
+                    },
+                }
+            }
+        };
+        let update = match (&self.on_conflict.update, self.on_conflict.do_nothing) {
+            (_, true) => vec![],
+            (Some(names), false) => names
+                .iter()
+                .map(|name| {
+                    self.columns
+                        .get(name)
+                        .filter(|col| is_inserted(col) && !target.contains(col))
+                        .ok_or_else(|| Error::InvalidConflictUpdate {
+                            table: self.name.to_string().into(),
+                            column: name.clone(),
+                        })
+                })
+                .collect::<Result<_, _>>()?,
+            // An entry leaving a defaulted column to `DEFAULT` would overwrite the existing value.
+            (None, false) => self
+                .new_columns()
+                .filter(|col| !target.contains(col) && !col.has_default())
+                .collect(),
+        };
+        Ok(Some((target, update)))
+    }
+
+    pub fn build_type(&self) -> Result<String, Error> {
+        self.check()?;
+        Ok(format!("{}", self))
+    }
+
+    /// Definitions of the user-defined types (enums and composites) used by the columns.
+    pub fn build_user_types(&self) -> Result<String, Error> {
+        let mut definitions = IndexMap::new();
+        self.collect_user_types(&mut definitions)?;
+        Ok(definitions.into_values().collect())
+    }
+
+    /// Add the definitions of the user-defined types used by the columns, keyed by schema
+    /// and Postgres name.
+    pub(crate) fn collect_user_types(
+        &self,
+        definitions: &mut IndexMap<(String, String), String>,
+    ) -> Result<(), Error> {
+        self.check()?;
+        for col in self.columns.values() {
+            col.r#type.collect_definitions(
+                definitions,
+                self.backend,
+                &self.attributes.global_derives(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn build_new_type(&self) -> Result<String, Error> {
+        self.check()?;
+        let columns = self.new_columns().fold(String::new(), |mut acc, col| {
+            acc.push_str(&self.build_field_attributes(col));
+            acc.push_str(&format!(
+                "    pub {},",
+                NewValue {
+                    val: col,
+                    lifetime: Some("a")
+                }
+            ));
+            acc.push('\n');
+            acc
+        });
+
+        Ok(format!(
+            r#"{}pub struct {}New{} {{
 {}}}
-        "#,
+{}"#,
+            match self.backend {
+                Backend::Diesel if self.new_columns().next().is_some() => self
+                    .build_struct_attributes(
+                        &["diesel::Insertable"],
+                        &format!("#[diesel(table_name = {})]\n", AsSnakeCase(&self.name)),
+                    ),
+                _ => self.build_struct_attributes(&[], ""),
+            },
             AsUpperCamelCase(&self.name),
-            columns
+            if self.new_type_borrows() { "<'a>" } else { "" },
+            columns,
+            self.build_validate(),
+        ))
+    }
+
+    /// The derive and the other attributes of a generated struct: the configured ones
+    /// around `derives` and `attributes` of the backend.
+    fn build_struct_attributes(&self, derives: &[&str], attributes: &str) -> String {
+        let mut derives = self
+            .attributes
+            .derives(&self.schema, &self.name)
+            .into_iter()
+            .filter(|derive| !derives.contains(derive))
+            .chain(derives.iter().copied())
+            .peekable();
+        let mut header = String::new();
+        if derives.peek().is_some() {
+            header.push_str(&format!(
+                "#[derive({})]\n",
+                derives.collect::<Vec<_>>().join(", ")
+            ));
+        }
+        header.push_str(attributes);
+        for attribute in self.attributes.attributes(&self.schema, &self.name) {
+            header.push_str(attribute);
+            header.push('\n');
+        }
+        header
+    }
+
+    /// The configured attributes of the field of `col`.
+    fn build_field_attributes(&self, col: &Column) -> String {
+        self.attributes
+            .column_attributes(&self.schema, &self.name, &col.name)
+            .fold(String::new(), |acc, attribute| {
+                acc + "    " + attribute + "\n"
+            })
+    }
+
+    /// A `validate()` method for the `New` struct, covering the CHECK constraints simple
+    /// enough to evaluate in Rust. Empty if there are none.
+    fn build_validate(&self) -> String {
+        let mut body = String::new();
+        for constraint in &self.constraints {
+            let Constraint::Check { name, expression } = constraint else {
+                continue;
+            };
+            for check in check::parse(expression).unwrap_or_default() {
+                let Some(col) = self.columns.get(check.column.as_str()) else {
+                    continue;
+                };
+                if !is_inserted(col) {
+                    continue;
+                }
+                let field = format!("self.{}", AsSnakeCase(&col.name));
+                // Defaulted fields are wrapped in another `Option`, `None` for `DEFAULT`.
+                let pattern = match (col.null, col.has_default()) {
+                    (false, false) => None,
+                    (true, true) => Some("Some(Some(value))"),
+                    _ => Some("Some(value)"),
+                };
+                let Some(violation) = check.violation(col, pattern.map_or(&field, |_| "value"))
+                else {
+                    continue;
+                };
+                let test = |indent: &str| {
+                    format!(
+                        "{indent}if {violation} {{\n{indent}    return Err({name:?});\n{indent}}}\n"
+                    )
+                };
+                body.push_str(&match pattern {
+                    Some(pattern) => format!(
+                        "        if let {pattern} = {field} {{\n{}        }}\n",
+                        test("            ")
+                    ),
+                    None => test("        "),
+                });
+            }
+        }
+        if body.is_empty() {
+            return body;
+        }
+
+        format!(
+            r#"impl {}New{} {{
+    /// Check the fields against the table's CHECK constraints, returning the name of the
+    /// first one that fails.
+    pub fn validate(&self) -> Result<(), &'static str> {{
+{body}        Ok(())
+    }}
+}}
+"#,
+            AsUpperCamelCase(&self.name),
+            if self.new_type_borrows() { "<'_>" } else { "" },
         )
     }
 
-    pub fn build_type_methods(&self) -> String {
-        let mut sql_statement = format!("INSERT INTO {}(", self.name);
+    /// The columns of the `New` struct, which are inserted into the table.
+    fn new_columns(&self) -> impl Iterator<Item = &Column> {
+        self.columns.values().filter(|c| is_inserted(c))
+    }
+
+    /// The columns `update` writes: all but the primary key and generated columns.
+    fn updated_columns(&self) -> impl Iterator<Item = &Column> {
+        self.new_columns().filter(|c| !c.primary_key)
+    }
+
+    /// Whether the `New` struct borrows any of its fields, and thus needs a lifetime.
+    fn new_type_borrows(&self) -> bool {
+        self.new_columns().any(|c| !c.r#type.is_copy())
+    }
+
+    pub fn build_type_methods(&self) -> Result<String, Error> {
+        self.check()?;
+        Ok(self.build_methods(&self.build_insert_body("", "", false)))
+    }
+
+    /// The body of `insert_slice` for the backend, with `suffix` added to each statement,
+    /// or `diesel_suffix` to the diesel query. If `returning`, the statements return rows,
+    /// which are pushed to `rows`.
+    fn build_insert_body(&self, suffix: &str, diesel_suffix: &str, returning: bool) -> String {
+        match self.backend {
+            Backend::Postgres | Backend::TokioPostgres => self.build_insert(suffix, returning),
+            Backend::Sqlx => self.build_sqlx_insert(suffix, returning),
+            Backend::Diesel => self.build_diesel_insert(diesel_suffix, returning),
+        }
+    }
+
+    /// The body of `insert_slice` for [`Backend::Postgres`] and [`Backend::TokioPostgres`],
+    /// with `suffix` added to each statement.
+    fn build_insert(&self, suffix: &str, returning: bool) -> String {
+        let await_ = self.backend.await_suffix();
+        let run = |statement: &str, params: &str| match returning {
+            true => format!(
+                "rows.push(Self::try_from(&client.query_one({statement}, {params}){await_}?)?);"
+            ),
+            false => format!("client.execute({statement}, {params}){await_}?;"),
+        };
         let parameters = self
-            .columns
-            .values()
-            .filter(|c| !c.primary_key)
-            .map(|c| c.name.as_ref())
-            .collect::<Vec<&str>>();
+            .new_columns()
+            .map(|c| quote_ident(&c.name))
+            .collect::<Vec<_>>();
+        if parameters.is_empty() {
+            return format!(
+                r#"let statement = client.prepare({:?}){await_}?;
+            for _ in slice {{
+                {}
+            }}"#,
+                format!(
+                    "INSERT INTO {} DEFAULT VALUES{suffix};",
+                    self.qualified_name()
+                ),
+                run("&statement", "&[]")
+            );
+        }
+
+        let mut sql_statement = format!("INSERT INTO {}(", self.qualified_name());
         for p in parameters.iter() {
             sql_statement.push_str(p);
             sql_statement.push_str(", ");
@@ -81,38 +416,857 @@ impl StructBuilder {
             sql_statement.pop();
         }
         sql_statement.push_str(") VALUES(");
-        for i in 0..parameters.len() {
-            sql_statement.push_str(&format!("${}, ", i + 1));
+
+        // Columns with a default are only known to be inserted once we look at the entry.
+        if self.new_columns().any(|c| c.has_default()) {
+            let mut params = String::new();
+            for col in self.new_columns() {
+                let placeholder = format!(
+                    r#"values.push(format!("${{}}{}", params.len()));"#,
+                    cast(col)
+                );
+                let field = AsSnakeCase(&col.name);
+                params.push_str(&match col.has_default() {
+                    true => format!(
+                        r#"
+                match &entry.{field} {{
+                    Some(value) => {{
+                        params.push(value);
+                        {placeholder}
+                    }}
+                    None => values.push("DEFAULT".to_string()),
+                }}"#
+                    ),
+                    false => format!(
+                        r#"
+                params.push(&entry.{field});
+                {placeholder}"#
+                    ),
+                });
+            }
+            let client_crate = self.backend.crate_path();
+            let template = format!("{{}}{{}}){};", suffix.replace('{', "{{").replace('}', "}}"));
+            let run = run("&sql_statement", "&params");
+            return format!(
+                r#"for entry in slice {{
+                let mut params: Vec<&(dyn {client_crate}::types::ToSql + Sync)> = Vec::new();
+                let mut values = Vec::new();{params}
+                let sql_statement = format!({template:?}, {sql_statement:?}, values.join(", "));
+                {run}
+            }}"#
+            );
+        }
+
+        for (i, col) in self.new_columns().enumerate() {
+            sql_statement.push_str(&format!("${}{}, ", i + 1, cast(col)));
         }
         if sql_statement.ends_with(", ") {
             sql_statement.pop();
             sql_statement.pop();
         }
-        sql_statement.push_str(");");
-        let mut fields =
-            self.columns
-                .values()
-                .filter(|c| !c.primary_key)
-                .fold(String::new(), |mut acc, col| {
-                    acc.push_str(&format!("&entry.{}, ", AsSnakeCase(&col.name)));
-                    acc
-                });
+        sql_statement.push_str(&format!("){suffix};"));
+        let mut fields = self.new_columns().fold(String::new(), |mut acc, col| {
+            acc.push_str(&format!("&entry.{}, ", AsSnakeCase(&col.name)));
+            acc
+        });
         if fields.ends_with(", ") {
             fields.pop();
             fields.pop();
         }
+        let run = run("&statement", &format!("&[{fields}]"));
         format!(
-            r#"impl {0} {{
-        pub fn insert_slice(client: &mut postgres::Client, slice: &[{0}New<'_>]) -> Result<(), postgres::Error> {{
-            let statement = client.prepare("{sql_statement}")?;
+            r#"let statement = client.prepare({sql_statement:?}){await_}?;
             for entry in slice {{
-                client.execute(&statement, &[{fields}])?;
-            }}
+                {run}
+            }}"#
+        )
+    }
+
+    /// The body of `insert_slice` for [`Backend::Sqlx`], binding the values of each entry,
+    /// with `suffix` added to each statement.
+    fn build_sqlx_insert(&self, suffix: &str, returning: bool) -> String {
+        let table = self.qualified_name();
+        let columns = self
+            .new_columns()
+            .map(|c| quote_ident(&c.name))
+            .collect::<Vec<_>>();
+        if columns.is_empty() {
+            let statement = format!("INSERT INTO {table} DEFAULT VALUES{suffix};");
+            return match returning {
+                true => format!(
+                    r#"for _ in slice {{
+                rows.push(sqlx::query_as::<_, Self>({statement:?}).fetch_one(&mut *conn).await?);
+            }}"#
+                ),
+                false => format!(
+                    r#"for _ in slice {{
+                sqlx::query({statement:?}).execute(&mut *conn).await?;
+            }}"#
+                ),
+            };
+        }
+        let prefix = format!("INSERT INTO {table}({}) VALUES(", columns.join(", "));
+
+        // Columns with a default are only known to be inserted once we look at the entry.
+        if self.new_columns().any(|c| c.has_default()) {
+            let mut values = String::new();
+            for col in self.new_columns() {
+                let bind = |indent: &str, value: &str| {
+                    let mut bind = format!("\n{indent}values.push_bind({value});");
+                    let cast = cast(col);
+                    if !cast.is_empty() {
+                        bind.push_str(&format!("\n{indent}values.push_unseparated({cast:?});"));
+                    }
+                    bind
+                };
+                let field = AsSnakeCase(&col.name);
+                match col.has_default() {
+                    true => {
+                        values.push_str(&format!(
+                            "\n                match &entry.{field} {{\n                    Some(value) => {{"
+                        ));
+                        values.push_str(&bind("                        ", "value"));
+                        values.push_str(
+                            r#"
+                    }
+                    None => {
+                        values.push("DEFAULT");
+                    }
+                }"#,
+                        );
+                    }
+                    false => values.push_str(&bind("                ", &format!("&entry.{field}"))),
+                }
+            }
+            return format!(
+                r#"for entry in slice {{
+                let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new({prefix:?});
+                let mut values = query.separated(", ");{values}
+                query.push({:?});
+                {}
+            }}"#,
+                format!("){suffix};"),
+                match returning {
+                    true =>
+                        "rows.push(query.build_query_as::<Self>().fetch_one(&mut *conn).await?);",
+                    false => "query.build().execute(&mut *conn).await?;",
+                }
+            );
+        }
+
+        let placeholders = self
+            .new_columns()
+            .enumerate()
+            .map(|(i, col)| format!("${}{}", i + 1, cast(col)))
+            .collect::<Vec<_>>();
+        let binds = self.new_columns().fold(String::new(), |mut acc, col| {
+            acc.push_str(&format!(
+                "\n                    .bind(&entry.{})",
+                AsSnakeCase(&col.name)
+            ));
+            acc
+        });
+        let statement = format!("{prefix}{}){suffix};", placeholders.join(", "));
+        match returning {
+            true => format!(
+                r#"for entry in slice {{
+                let row = sqlx::query_as::<_, Self>({statement:?}){binds}
+                    .fetch_one(&mut *conn)
+                    .await?;
+                rows.push(row);
+            }}"#
+            ),
+            false => format!(
+                r#"for entry in slice {{
+                sqlx::query({statement:?}){binds}
+                    .execute(&mut *conn)
+                    .await?;
+            }}"#
+            ),
+        }
+    }
+
+    /// The body of `insert_slice` for [`Backend::Diesel`], which inserts all entries at once,
+    /// with `suffix` added to the query.
+    fn build_diesel_insert(&self, suffix: &str, returning: bool) -> String {
+        let table = AsSnakeCase(&self.name);
+        match (self.new_columns().next(), returning) {
+            (Some(_), false) => format!(
+                "diesel::RunQueryDsl::execute(diesel::insert_into({table}::table).values(slice){suffix}, conn)?;"
+            ),
+            (None, false) => format!(
+                r#"for _ in slice {{
+                diesel::RunQueryDsl::execute(diesel::insert_into({table}::table).default_values(){suffix}, conn)?;
+            }}"#
+            ),
+            (Some(_), true) => format!(
+                r#"use diesel::prelude::*;
+            rows.extend(diesel::insert_into({table}::table).values(slice){suffix}.get_results(conn)?);"#
+            ),
+            (None, true) => format!(
+                r#"use diesel::prelude::*;
+            for _ in slice {{
+                rows.push(diesel::insert_into({table}::table).default_values(){suffix}.get_result(conn)?);
+            }}"#
+            ),
+        }
+    }
+
+    /// The `diesel::table!` of the table for [`Backend::Diesel`], with `joinable!` and
+    /// `allow_tables_to_appear_in_same_query!` for each table it has a single foreign key
+    /// to, preceded by a `sql_types` module declaring the user-defined types of the columns.
+    ///
+    /// The referenced tables must be declared next to it. For several tables, use
+    /// [`DatabaseBuilder`](crate::DatabaseBuilder), which declares them all in one module.
+    pub fn build_diesel_schema(&self) -> Result<String, Error> {
+        let mut schema = String::new();
+        let mut sql_types = IndexMap::new();
+        self.collect_diesel_sql_types(&mut sql_types);
+        if !sql_types.is_empty() {
+            schema.push_str(&format!(
+                "pub mod sql_types {{\n{}}}\n\n",
+                sql_types.into_values().collect::<Vec<_>>().join("\n")
+            ));
+        }
+        schema.push_str(&self.build_diesel_table()?);
+        let table = AsSnakeCase(&self.name);
+        for (to_table, col) in self.diesel_joins() {
+            schema.push_str(&format!(
+                "\ndiesel::joinable!({table} -> {} ({}));\n",
+                AsSnakeCase(to_table),
+                AsSnakeCase(&col.name)
+            ));
+            schema.push_str(&format!(
+                "diesel::allow_tables_to_appear_in_same_query!({table}, {});\n",
+                AsSnakeCase(to_table)
+            ));
+        }
+        Ok(schema)
+    }
+
+    /// The `diesel::table!` of [`build_diesel_schema()`](Self::build_diesel_schema) alone.
+    pub(crate) fn build_diesel_table(&self) -> Result<String, Error> {
+        self.check()?;
+        let primary_key = self.primary_key();
+        if primary_key.is_empty() {
+            return Err(Error::MissingPrimaryKey {
+                table: self.name.to_string().into(),
+            });
+        }
+        let mut imports = String::from("    use diesel::sql_types::*;\n");
+        let mut sql_types = IndexMap::new();
+        self.collect_diesel_sql_types(&mut sql_types);
+        for name in sql_types.keys() {
+            imports.push_str(&format!("    use super::sql_types::{name};\n"));
+        }
+
+        let sql_name = |name: &str, indent: &str| match AsSnakeCase(name).to_string() == name {
+            true => String::new(),
+            false => format!("{indent}#[sql_name = {name:?}]\n"),
+        };
+        let mut columns = String::new();
+        for col in self.columns.values() {
+            for line in col.comment.iter().flat_map(|c| c.lines()) {
+                columns.push_str(&format!("        /// {}\n", line).replace("/// \n", "///\n"));
+            }
+            columns.push_str(&sql_name(&col.name, "        "));
+            let sql_type = col.r#type.diesel_sql_type(false).unwrap_or_default();
+            columns.push_str(&format!(
+                "        {} -> {},\n",
+                AsSnakeCase(&col.name),
+                match col.null {
+                    true => format!("Nullable<{sql_type}>"),
+                    false => sql_type,
+                }
+            ));
+        }
+        Ok(format!(
+            r#"diesel::table! {{
+{imports}
+{}    {}{} ({}) {{
+{columns}    }}
+}}
+"#,
+            sql_name(&self.name, "    "),
+            match self.schema.as_ref() {
+                "public" => String::new(),
+                schema => format!("{schema}."),
+            },
+            AsSnakeCase(&self.name),
+            primary_key
+                .iter()
+                .map(|col| AsSnakeCase(&col.name).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+
+    /// Add the `sql_types` declarations the columns need for diesel, keyed by name.
+    pub(crate) fn collect_diesel_sql_types(&self, sql_types: &mut IndexMap<String, String>) {
+        for col in self.columns.values() {
+            col.r#type.collect_diesel_sql_types(sql_types);
+        }
+    }
+
+    /// The tables this one can be joined to with diesel, with the column referencing each.
+    /// diesel only supports a single-column foreign key to another table per table pair.
+    pub(crate) fn diesel_joins(&self) -> Vec<(&str, &Column)> {
+        let references = self
+            .columns
+            .values()
+            .filter_map(|col| Some((col.foreign_key.as_ref()?, col)))
+            .filter(|(fk, _)| fk.to_table != self.name || fk.to_schema != self.schema)
+            .collect::<Vec<_>>();
+        references
+            .iter()
+            .filter(|(fk, _)| {
+                fk.columns.len() == 1
+                    && references
+                        .iter()
+                        .filter(|(other, _)| other.to_table == fk.to_table)
+                        .count()
+                        == 1
+            })
+            .map(|(fk, col)| (fk.to_table.as_ref(), *col))
+            .collect()
+    }
+
+    /// The columns of the primary key, in key order if the constraint is known.
+    pub(crate) fn primary_key(&self) -> Vec<&Column> {
+        for constraint in &self.constraints {
+            if let Constraint::PrimaryKey { columns, .. } = constraint {
+                return columns
+                    .iter()
+                    .filter_map(|name| self.columns.get(name))
+                    .collect();
+            }
+        }
+        self.columns
+            .values()
+            .filter(|col| col.primary_key)
+            .collect()
+    }
+
+    /// The schema-qualified name of the table, quoted for use in SQL.
+    fn qualified_name(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+
+    /// The methods of the struct, around `insert_body`, the body of `insert_slice`.
+    fn build_methods(&self, insert_body: &str) -> String {
+        let asyncness = self.backend.asyncness();
+        let (client, acquire) = match self.backend {
+            Backend::Postgres => ("client: &mut postgres::Client", ""),
+            Backend::TokioPostgres => ("client: &impl tokio_postgres::GenericClient", ""),
+            Backend::Sqlx => (
+                "conn: impl sqlx::Acquire<'_, Database = sqlx::Postgres>",
+                "let mut conn = conn.acquire().await?;\n            ",
+            ),
+            Backend::Diesel => ("conn: &mut diesel::PgConnection", ""),
+        };
+        let columns = match self.backend {
+            // Diesel selects the columns of the `table!` through `Selectable`.
+            Backend::Diesel => String::new(),
+            _ => format!(
+                r#"/// The columns of the table in the order of the fields, as a list to select.
+        pub const COLUMNS: &'static str = {:?};
+
+        "#,
+                self.select_list()
+            ),
+        };
+        format!(
+            r#"impl {0} {{
+        {columns}pub {asyncness}fn insert_slice({client}, slice: &[{0}New{1}]) -> Result<(), {2}> {{
+            {acquire}{insert_body}
             Ok(())
         }}
+{3}}}
+{4}        "#,
+            AsUpperCamelCase(&self.name),
+            if self.new_type_borrows() { "<'_>" } else { "" },
+            self.backend.error_path(),
+            self.build_returning_methods(client, acquire)
+                + &self.build_upsert_methods(client, acquire)
+                + &self.build_key_methods(client, acquire)
+                + &self.build_unique_methods(client, acquire),
+            self.build_try_from_row(),
+        )
+    }
+
+    /// `insert_slice_returning` and `insert_returning`, inserting entries like `insert_slice`
+    /// and returning the rows with the values Postgres filled in.
+    fn build_returning_methods(&self, client: &str, acquire: &str) -> String {
+        format!(
+            r#"
+        /// Like `insert_slice`, returning the inserted rows with their generated and default
+        /// values.
+        pub {asyncness}fn insert_slice_returning({client}, slice: &[{0}New{1}]) -> Result<Vec<Self>, {2}> {{
+            {acquire}let mut rows = Vec::with_capacity(slice.len());
+            {3}
+            Ok(rows)
+        }}
+
+        /// Like `insert_slice_returning`, for a single entry.
+        pub {asyncness}fn insert_returning({client}, entry: &{0}New{1}) -> Result<Self, {2}> {{
+            let mut rows = Self::insert_slice_returning({4}, std::slice::from_ref(entry)){await_}?;
+            Ok(rows.remove(0))
+        }}
+"#,
+            AsUpperCamelCase(&self.name),
+            if self.new_type_borrows() { "<'_>" } else { "" },
+            self.backend.error_path(),
+            self.build_insert_body(
+                &format!(" RETURNING {}", self.select_list()),
+                ".returning(Self::as_returning())",
+                true
+            ),
+            match self.backend {
+                Backend::Sqlx | Backend::Diesel => "conn",
+                Backend::Postgres | Backend::TokioPostgres => "client",
+            },
+            asyncness = self.backend.asyncness(),
+            await_ = self.backend.await_suffix(),
+        )
+    }
+
+    /// `upsert_slice` and `upsert`, inserting entries with an `ON CONFLICT` clause. Empty
+    /// without a key to detect conflicts on.
+    fn build_upsert_methods(&self, client: &str, acquire: &str) -> String {
+        let Ok(Some((target, update))) = self.conflict_columns() else {
+            return String::new();
+        };
+        let (asyncness, await_) = (self.backend.asyncness(), self.backend.await_suffix());
+        let diesel_table = AsSnakeCase(&self.name).to_string();
+        let diesel_column = |col: &Column| format!("{diesel_table}::{}", AsSnakeCase(&col.name));
+        let columns = |key: &[&Column]| {
+            key.iter()
+                .map(|col| format!("`{}`", col.name))
+                .collect::<Vec<_>>()
+                .join(" and ")
+        };
+        let conflict = format!(
+            " ON CONFLICT ({})",
+            target
+                .iter()
+                .map(|col| quote_ident(&col.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let diesel_conflict = match target.as_slice() {
+            [col] => format!(".on_conflict({})", diesel_column(col)),
+            target => format!(
+                ".on_conflict(({}))",
+                target
+                    .iter()
+                    .map(|col| diesel_column(col))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let (suffix, diesel_suffix, doc) = match update.is_empty() {
+            true => (
+                format!("{conflict} DO NOTHING"),
+                format!("{diesel_conflict}.do_nothing()"),
+                format!(
+                    "Insert the entries, skipping those with the same {} as an existing row.",
+                    columns(&target)
+                ),
+            ),
+            false => (
+                format!(
+                    "{conflict} DO UPDATE SET {}",
+                    update
+                        .iter()
+                        .map(|col| format!("{0} = EXCLUDED.{0}", quote_ident(&col.name)))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+                format!(
+                    "{diesel_conflict}.do_update().set(({}))",
+                    update
+                        .iter()
+                        .map(|col| format!(
+                            "diesel::ExpressionMethods::eq({0}, diesel::upsert::excluded({0})),",
+                            diesel_column(col)
+                        ))
+                        .collect::<String>()
+                ),
+                format!(
+                    "Insert the entries, or update the existing row with the same {} instead.",
+                    columns(&target)
+                ),
+            ),
+        };
+        format!(
+            r#"
+        /// {doc}
+        pub {asyncness}fn upsert_slice({client}, slice: &[{0}New{1}]) -> Result<(), {2}> {{
+            {acquire}{3}
+            Ok(())
+        }}
+
+        /// Like `upsert_slice`, for a single entry.
+        pub {asyncness}fn upsert({client}, entry: &{0}New{1}) -> Result<(), {2}> {{
+            Self::upsert_slice({4}, std::slice::from_ref(entry)){await_}
+        }}
+"#,
+            AsUpperCamelCase(&self.name),
+            if self.new_type_borrows() { "<'_>" } else { "" },
+            self.backend.error_path(),
+            self.build_insert_body(&suffix, &diesel_suffix, false),
+            match self.backend {
+                Backend::Sqlx | Backend::Diesel => "conn",
+                Backend::Postgres | Backend::TokioPostgres => "client",
+            },
+        )
+    }
+
+    /// `get`, `get_many`, `update`, `delete` and `exists` by primary key, taking `client`
+    /// and starting with `acquire` like `insert_slice`. Empty without a primary key.
+    fn build_key_methods(&self, client: &str, acquire: &str) -> String {
+        let key = self.primary_key();
+        if key.is_empty() {
+            return String::new();
+        }
+        let (asyncness, await_) = (self.backend.asyncness(), self.backend.await_suffix());
+        let error = self.backend.error_path();
+        let table = self.qualified_name();
+        let fields = key
+            .iter()
+            .map(|col| AsSnakeCase(&col.name).to_string())
+            .collect::<Vec<_>>();
+        let params = key_params(&key);
+        let key_type = match key.as_slice() {
+            [col] => param_type(col),
+            key => format!(
+                "({})",
+                key.iter()
+                    .map(|col| param_type(col))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let condition = key_condition(&key);
+        // A single column is looked up at once, a composite key one by one.
+        let get_many = match key.as_slice() {
+            [col] => Some(format!(
+                "{} {} = ANY($1{});",
+                self.select_where(),
+                quote_ident(&col.name),
+                array_cast(col)
+            )),
+            _ => None,
+        };
+        let update = self.updated_columns().next().map(|_| {
+            let assignments = self
+                .updated_columns()
+                .enumerate()
+                .map(|(i, col)| {
+                    format!(
+                        "{} = ${}{}",
+                        quote_ident(&col.name),
+                        key.len() + i + 1,
+                        cast(col)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("UPDATE {table} SET {assignments} WHERE {condition};")
+        });
+        let delete = format!("DELETE FROM {table} WHERE {condition};");
+        let exists = format!("SELECT EXISTS(SELECT 1 FROM {table} WHERE {condition});");
+
+        let diesel_table = AsSnakeCase(&self.name).to_string();
+        let find = |values: Vec<String>| match values.as_slice() {
+            [value] => format!("{diesel_table}::table.find({value})"),
+            values => format!("{diesel_table}::table.find(({}))", values.join(", ")),
+        };
+        let self_key = key
+            .iter()
+            .map(|col| match col.r#type.is_copy() {
+                true => format!("self.{}", AsSnakeCase(&col.name)),
+                false => format!("&self.{}", AsSnakeCase(&col.name)),
+            })
+            .collect::<Vec<_>>();
+        let self_values = key
+            .iter()
+            .copied()
+            .chain(self.updated_columns())
+            .map(|col| format!("&self.{}", AsSnakeCase(&col.name)))
+            .collect::<Vec<_>>();
+        let args = fields
+            .iter()
+            .map(|field| format!("&{field}"))
+            .collect::<Vec<_>>();
+
+        let (get_many_body, update_body, delete_body, exists_body) = match self.backend {
+            Backend::Postgres | Backend::TokioPostgres => (
+                get_many.map(|sql| format!(
+                    "client\n                .query({sql:?}, &[&keys]){await_}?\n                .iter()\n                .map(Self::try_from)\n                .collect()"
+                )),
+                update.as_ref().map(|sql| format!(
+                    "Ok(client.execute({sql:?}, &[{}]){await_}? > 0)",
+                    self_values.join(", ")
+                )),
+                format!(
+                    "Ok(client.execute({delete:?}, &[{}]){await_}? > 0)",
+                    args.join(", ")
+                ),
+                format!(
+                    "client.query_one({exists:?}, &[{}]){await_}?.try_get(0)",
+                    args.join(", ")
+                ),
+            ),
+            Backend::Sqlx => (
+                get_many.map(|sql| format!(
+                    "sqlx::query_as::<_, Self>({sql:?})\n                .bind(keys)\n                .fetch_all(&mut *conn)\n                .await"
+                )),
+                update.as_ref().map(|sql| format!(
+                    "let result = sqlx::query({sql:?}){}\n                .execute(&mut *conn)\n                .await?;\n            Ok(result.rows_affected() > 0)",
+                    binds(&self_values)
+                )),
+                format!(
+                    "let result = sqlx::query({delete:?}){}\n                .execute(&mut *conn)\n                .await?;\n            Ok(result.rows_affected() > 0)",
+                    binds(&fields)
+                ),
+                format!(
+                    "sqlx::query_scalar::<_, bool>({exists:?}){}\n                .fetch_one(&mut *conn)\n                .await",
+                    binds(&fields)
+                ),
+            ),
+            Backend::Diesel => (
+                match key.as_slice() {
+                    [col] => Some(format!(
+                        "use diesel::prelude::*;\n            {diesel_table}::table\n                .filter({diesel_table}::{}.eq_any(keys.iter().copied()))\n                .select(Self::as_select())\n                .load(conn)",
+                        AsSnakeCase(&col.name)
+                    )),
+                    _ => None,
+                },
+                self.updated_columns().next().map(|_| {
+                    let changes = self
+                        .updated_columns()
+                        .map(|col| {
+                            format!(
+                                "\n                    {diesel_table}::{0}.eq(&self.{0}),",
+                                AsSnakeCase(&col.name)
+                            )
+                        })
+                        .collect::<String>();
+                    format!(
+                        "use diesel::prelude::*;\n            let count = diesel::update({})\n                .set(({changes}\n                ))\n                .execute(conn)?;\n            Ok(count > 0)",
+                        find(self_key.clone())
+                    )
+                }),
+                format!(
+                    "use diesel::prelude::*;\n            Ok(diesel::delete({}).execute(conn)? > 0)",
+                    find(fields.clone())
+                ),
+                format!(
+                    "use diesel::prelude::*;\n            diesel::select(diesel::dsl::exists({})).get_result(conn)",
+                    find(fields.clone())
+                ),
+            ),
+        };
+        let get_many_body = get_many_body.unwrap_or_else(|| {
+            let conn = match self.backend {
+                Backend::Sqlx => "&mut *conn",
+                Backend::Postgres | Backend::TokioPostgres => "client",
+                Backend::Diesel => "conn",
+            };
+            format!(
+                r#"let mut rows = Vec::new();
+            for &({0}) in keys {{
+                rows.extend(Self::get({conn}, {0}){await_}?);
+            }}
+            Ok(rows)"#,
+                fields.join(", ")
+            )
+        });
+        let mut methods = self.build_find(
+            "get",
+            "The row with the given primary key, if there is one.",
+            &key,
+            (client, acquire),
+            find(fields.clone()),
+        );
+        methods.push_str(&format!(
+            r#"
+        /// The rows with the given primary keys, skipping those without one.
+        pub {asyncness}fn get_many({client}, keys: &[{key_type}]) -> Result<Vec<Self>, {error}> {{
+            {acquire}{get_many_body}
+        }}
+"#
+        ));
+        if let Some(update_body) = update_body {
+            methods.push_str(&format!(
+                r#"
+        /// Write the fields of `self` to the row with its primary key, returning whether
+        /// there is one.
+        pub {asyncness}fn update(&self, {client}) -> Result<bool, {error}> {{
+            {acquire}{update_body}
+        }}
+"#
+            ));
+        }
+        methods.push_str(&format!(
+            r#"
+        /// Delete the row with the given primary key, returning whether there was one.
+        pub {asyncness}fn delete({client}, {params}) -> Result<bool, {error}> {{
+            {acquire}{delete_body}
+        }}
+
+        /// Whether there is a row with the given primary key.
+        pub {asyncness}fn exists({client}, {params}) -> Result<bool, {error}> {{
+            {acquire}{exists_body}
+        }}
+"#
+        ));
+        methods
+    }
+
+    /// A `find_by_` method for each set of unique columns other than the primary key, like
+    /// `find_by_email` or `find_by_warehouse_and_bin`.
+    fn build_unique_methods(&self, client: &str, acquire: &str) -> String {
+        let table = AsSnakeCase(&self.name).to_string();
+        self.unique_keys()
+            .into_iter()
+            .map(|key| {
+                let fields = key
+                    .iter()
+                    .map(|col| AsSnakeCase(&col.name).to_string())
+                    .collect::<Vec<_>>();
+                let columns = key
+                    .iter()
+                    .map(|col| format!("`{}`", col.name))
+                    .collect::<Vec<_>>()
+                    .join(" and ");
+                let source = fields.iter().fold(format!("{table}::table"), |acc, field| {
+                    format!("{acc}\n                .filter({table}::{field}.eq({field}))")
+                });
+                self.build_find(
+                    &format!("find_by_{}", fields.join("_and_")),
+                    &format!("The row with the given {columns}, if there is one."),
+                    &key,
+                    (client, acquire),
+                    source,
+                )
+            })
+            .collect()
+    }
+
+    /// The columns of each unique constraint and index other than the primary key, once.
+    fn unique_keys(&self) -> Vec<Vec<&Column>> {
+        let primary_key = self.primary_key();
+        let mut keys: Vec<Vec<&Column>> = Vec::new();
+        for constraint in &self.constraints {
+            let Constraint::Unique { columns, .. } = constraint else {
+                continue;
+            };
+            let key = columns
+                .iter()
+                .filter_map(|name| self.columns.get(name))
+                .collect::<Vec<_>>();
+            if key.len() == columns.len() && key != primary_key && !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+        keys
+    }
+
+    /// A method called `name` returning the row whose `key` columns equal its parameters,
+    /// if there is one. For [`Backend::Diesel`], `source` selects the row from the table.
+    fn build_find(
+        &self,
+        name: &str,
+        doc: &str,
+        key: &[&Column],
+        (client, acquire): (&str, &str),
+        source: String,
+    ) -> String {
+        let fields = key
+            .iter()
+            .map(|col| AsSnakeCase(&col.name).to_string())
+            .collect::<Vec<_>>();
+        let sql = format!("{} {};", self.select_where(), key_condition(key));
+        let body = match self.backend {
+            Backend::Postgres | Backend::TokioPostgres => format!(
+                "let row = client.query_opt({sql:?}, &[{}]){}?;\n            row.as_ref().map(Self::try_from).transpose()",
+                fields
+                    .iter()
+                    .map(|field| format!("&{field}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.backend.await_suffix()
+            ),
+            Backend::Sqlx => format!(
+                "sqlx::query_as::<_, Self>({sql:?}){}\n                .fetch_optional(&mut *conn)\n                .await",
+                binds(&fields)
+            ),
+            Backend::Diesel => format!(
+                "use diesel::prelude::*;\n            {source}\n                .select(Self::as_select())\n                .first(conn)\n                .optional()"
+            ),
+        };
+        format!(
+            r#"
+        /// {doc}
+        pub {}fn {name}({client}, {}) -> Result<Option<Self>, {}> {{
+            {acquire}{body}
+        }}
+"#,
+            self.backend.asyncness(),
+            key_params(key),
+            self.backend.error_path(),
+        )
+    }
+
+    /// The start of a query for the columns of the table, up to `WHERE`.
+    fn select_where(&self) -> String {
+        format!(
+            "SELECT {} FROM {} WHERE",
+            self.select_list(),
+            self.qualified_name()
+        )
+    }
+
+    /// The columns of the table in the order of the fields, to select or return.
+    fn select_list(&self) -> String {
+        self.columns
+            .values()
+            .map(select)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// `TryFrom<&Row>` for the struct, decoding each field from the column of the same name.
+    /// Empty for backends that derive their own.
+    fn build_try_from_row(&self) -> String {
+        let row = match self.backend {
+            Backend::Postgres => "postgres::Row",
+            Backend::TokioPostgres => "tokio_postgres::Row",
+            Backend::Sqlx | Backend::Diesel => return String::new(),
+        };
+        let fields = self.columns.values().fold(String::new(), |mut acc, col| {
+            acc.push_str(&format!(
+                "            {}: row.try_get({:?})?,\n",
+                AsSnakeCase(&col.name),
+                col.name
+            ));
+            acc
+        });
+        format!(
+            r#"
+impl TryFrom<&{row}> for {} {{
+    type Error = {};
+
+    fn try_from(row: &{row}) -> Result<Self, Self::Error> {{
+        Ok(Self {{
+{fields}        }})
+    }}
 }}
-        "#,
+"#,
             AsUpperCamelCase(&self.name),
+            self.backend.error_path(),
         )
     }
 
@@ -165,56 +1319,571 @@ impl StructBuilder {
     */
 
     #[cfg(feature = "postgres")]
-    pub fn new_from_conn(
-        client: &mut postgres::Client,
-        table_name: &str,
-    ) -> Result<Self, anyhow::Error> {
+    pub fn new_from_conn(client: &mut postgres::Client, table_name: &str) -> Result<Self, Error> {
         let mut struct_bldr = Self::new(table_name.to_string().into());
+        struct_bldr.introspect(client)?;
+        Ok(struct_bldr)
+    }
+
+    /// Add the columns and constraints of the table called `self.name`.
+    #[cfg(feature = "postgres")]
+    pub fn introspect(&mut self, client: &mut postgres::Client) -> Result<&mut Self, Error> {
+        block_on(self.read_information_schema(&mut Blocking(client)))?;
+        Ok(self)
+    }
+
+    /// Like [`introspect()`](Self::introspect), through an async client.
+    #[cfg(feature = "tokio-postgres")]
+    pub async fn introspect_async(
+        &mut self,
+        client: &(impl tokio_postgres::GenericClient + Sync),
+    ) -> Result<&mut Self, Error> {
+        self.read_information_schema(&mut Async(client)).await?;
+        Ok(self)
+    }
+
+    #[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+    pub(crate) async fn read_information_schema(
+        &mut self,
+        client: &mut impl Client,
+    ) -> Result<(), Error> {
+        let table_name = self.name.clone();
+        let schema = self.schema.clone();
+        let overrides = &self.type_overrides;
         let mut col_index: IndexMap<String, Column> = IndexMap::new();
-        for row in client.query("SELECT column_name, is_nullable, data_type FROM information_schema.columns WHERE table_name = $1;", &[&table_name])? {
+        for row in client.query("SELECT column_name, is_nullable, data_type, udt_schema, udt_name, domain_name, column_default, identity_generation, is_generated, domain_schema FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2 ORDER BY ordinal_position;", &[&schema, &table_name]).await? {
         let column_name: &str = row.get(0);
         let is_nullable: &str = row.get(1);
         let data_type: &str = row.get(2);
-        let col = Column::new(column_name.to_string().into(), Type::from_str(data_type)?).set_null(is_nullable == "YES");
+        let domain_name: Option<&str> = row.get(5);
+        let mut r#type = match overrides
+            .column(&schema, &table_name, column_name)
+            .or_else(|| domain_name.and_then(|d| overrides.r#type(d)))
+        {
+            Some(r#type) => with_replaced(r#type, client, data_type, row.get(3), row.get(4)).await,
+            None => {
+                let r#type = resolve_type(client, overrides, data_type, row.get(3), row.get(4))
+                    .await
+                    .map_err(|err| err.in_column(&table_name, column_name))?;
+                match domain_name {
+                    Some(name) => Type::Domain {
+                        name: name.to_string().into(),
+                        schema: row.get::<_, String>(9).into(),
+                        inner: Box::new(r#type),
+                        mode: DomainMode::default(),
+                    },
+                    None => r#type,
+                }
+            }
+        };
+        r#type.set_time_crate(self.time_crate).set_domain_mode(self.domain_mode);
+        let col = Column::new(column_name.to_string().into(), r#type)
+            .set_null(is_nullable == "YES")
+            .set_domain(domain_name.map(|name| name.to_string().into()))
+            .set_default(row.get::<_, Option<String>>(6).map(|default| default.into()))
+            .set_identity(match row.get(7) {
+                Some("ALWAYS") => Some(Identity::Always),
+                Some("BY DEFAULT") => Some(Identity::ByDefault),
+                _ => None,
+            })
+            .set_generated(row.get::<_, &str>(8) == "ALWAYS");
         col_index.insert(column_name.to_string(), col);
     }
 
-        for row in client.query("SELECT a.column_name, a.constraint_name, b.constraint_type FROM information_schema.constraint_column_usage AS a JOIN information_schema.table_constraints AS b ON a.constraint_name = b.constraint_name WHERE a.table_name = $1", &[&table_name])? {
+        let mut primary_key = None::<(String, Vec<Cow<'static, str>>)>;
+        let mut uniques = IndexMap::<String, Vec<Cow<'static, str>>>::new();
+        for row in client.query("SELECT kcu.column_name, kcu.constraint_name, tc.constraint_type FROM information_schema.key_column_usage AS kcu JOIN information_schema.table_constraints AS tc ON tc.constraint_schema = kcu.constraint_schema AND tc.constraint_name = kcu.constraint_name AND tc.table_schema = kcu.table_schema AND tc.table_name = kcu.table_name WHERE kcu.table_schema = $1 AND kcu.table_name = $2 ORDER BY kcu.constraint_name, kcu.ordinal_position", &[&schema, &table_name]).await? {
         let column_name: &str = row.get(0);
         let constraint_name: &str = row.get(1);
         let constraint_type: &str = row.get(2);
         if let Some(col) = col_index.get_mut(&column_name.to_string()) {
             match constraint_type {
-                "UNIQUE" => {col.unique = true;},
-                "PRIMARY KEY" => {col.primary_key = true;},
-                other => panic!("unknown constraint type: {}", other),
+                "UNIQUE" => {
+                    col.unique = true;
+                    uniques.entry(constraint_name.to_string()).or_default().push(column_name.to_string().into());
+                },
+                "PRIMARY KEY" => {
+                    col.primary_key = true;
+                    primary_key.get_or_insert_with(|| (constraint_name.to_string(), vec![])).1.push(column_name.to_string().into());
+                },
+                // Handled below, together with the referenced columns.
+                "FOREIGN KEY" => {},
+                other => return Err(Error::UnknownConstraint {
+                    table: table_name,
+                    name: constraint_name.to_string().into(),
+                    constraint_type: other.to_string().into(),
+                }),
             }
         } else {
-            panic!("got constraint for unknown column: column_name {column_name}, constraint_name {constraint_name} constraint_type {constraint_type}");
+            return Err(Error::UnknownColumn {
+                table: table_name,
+                name: constraint_name.to_string().into(),
+                column: column_name.to_string().into(),
+            });
+        }
+    }
+
+        if let Some((name, columns)) = primary_key {
+            self.constraints.push(Constraint::PrimaryKey {
+                name: name.into(),
+                columns,
+            });
+        }
+        for (name, columns) in uniques {
+            self.constraints.push(Constraint::Unique {
+                name: name.into(),
+                columns,
+            });
+        }
+        self.read_unique_indexes(client, &mut col_index).await?;
+
+        // Constraint names are only unique per table, which `referential_constraints` can't
+        // tell apart, so foreign keys are read by table like the checks below.
+        for row in client.query("SELECT con.conname, ARRAY(SELECT a.attname FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, i) JOIN pg_attribute AS a ON a.attrelid = con.conrelid AND a.attnum = k.attnum ORDER BY k.i)::text[], fn.nspname, fc.relname, ARRAY(SELECT a.attname FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, i) JOIN pg_attribute AS a ON a.attrelid = con.confrelid AND a.attnum = k.attnum ORDER BY k.i)::text[] FROM pg_constraint AS con JOIN pg_class AS c ON c.oid = con.conrelid JOIN pg_namespace AS n ON n.oid = c.relnamespace JOIN pg_class AS fc ON fc.oid = con.confrelid JOIN pg_namespace AS fn ON fn.oid = fc.relnamespace WHERE n.nspname = $1 AND c.relname = $2 AND con.contype = 'f' ORDER BY con.conname", &[&schema, &table_name]).await? {
+        let name: String = row.get(0);
+        let columns: Vec<Cow<'static, str>> = row.get::<_, Vec<String>>(1).into_iter().map(Cow::from).collect();
+        let ref_schema: String = row.get(2);
+        let ref_table: String = row.get(3);
+        let ref_columns: Vec<Cow<'static, str>> = row.get::<_, Vec<String>>(4).into_iter().map(Cow::from).collect();
+        for column in &columns {
+            let col = col_index.get_mut(column.as_ref()).ok_or_else(|| Error::UnknownColumn {
+                table: table_name.clone(),
+                name: name.clone().into(),
+                column: column.clone(),
+            })?;
+            if col.foreign_key.is_none() {
+                col.foreign_key = Some(
+                    ForeignKey::new(ref_table.clone().into(), ref_columns.clone())
+                        .set_to_schema(ref_schema.clone().into()),
+                );
+            }
+        }
+        self.constraints.push(Constraint::ForeignKey {
+            name: name.into(),
+            columns: columns.into(),
+            ref_schema: ref_schema.into(),
+            ref_table: ref_table.into(),
+            ref_columns: ref_columns.into(),
+        });
+    }
+
+        for row in client.query("SELECT con.conname, pg_get_constraintdef(con.oid) FROM pg_constraint AS con JOIN pg_class AS cls ON cls.oid = con.conrelid JOIN pg_namespace AS n ON n.oid = cls.relnamespace WHERE n.nspname = $1 AND cls.relname = $2 AND con.contype = 'c' ORDER BY con.conname", &[&schema, &table_name]).await? {
+        let name: String = row.get(0);
+        let definition: &str = row.get(1);
+        self.constraints.push(Constraint::Check {
+            name: name.into(),
+            expression: check::expression(definition).to_string().into(),
+        });
+    }
+
+        for (_, col) in col_index.into_iter() {
+            self.add_column(col);
+        }
+
+        Ok(())
+    }
+
+    /// Add the unique indexes that don't back a constraint as [`Constraint::Unique`], if
+    /// they cover plain columns and aren't partial. Columns unique on their own are marked
+    /// as such in `col_index`.
+    #[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+    async fn read_unique_indexes(
+        &mut self,
+        client: &mut impl Client,
+        col_index: &mut IndexMap<String, Column>,
+    ) -> Result<(), Error> {
+        let (schema, table_name) = (self.schema.clone(), self.name.clone());
+        for row in client.query("SELECT i.relname, ARRAY(SELECT a.attname FROM unnest(x.indkey::int2[]) WITH ORDINALITY AS k(attnum, n) JOIN pg_attribute AS a ON a.attrelid = x.indrelid AND a.attnum = k.attnum WHERE k.n <= x.indnkeyatts ORDER BY k.n)::text[] FROM pg_index AS x JOIN pg_class AS i ON i.oid = x.indexrelid JOIN pg_class AS c ON c.oid = x.indrelid JOIN pg_namespace AS n ON n.oid = c.relnamespace WHERE n.nspname = $1 AND c.relname = $2 AND x.indisunique AND NOT x.indisprimary AND x.indpred IS NULL AND x.indexprs IS NULL AND NOT EXISTS (SELECT 1 FROM pg_constraint AS con WHERE con.conindid = x.indexrelid) ORDER BY i.relname", &[&schema, &table_name]).await? {
+            let name: String = row.get(0);
+            let columns: Vec<String> = row.get(1);
+            if let [column] = columns.as_slice() {
+                if let Some(col) = col_index.get_mut(column) {
+                    col.unique = true;
+                }
+            }
+            self.constraints.push(Constraint::Unique {
+                name: name.into(),
+                columns: columns.into_iter().map(Cow::from).collect(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Like [`introspect()`](Self::introspect), but reading `pg_catalog` instead of
+    /// `information_schema`. This is faster on large catalogs, and also fills in each
+    /// column's `type_def` (including its typmod, as in `numeric(10,2)`) and `comment`.
+    #[cfg(feature = "postgres")]
+    pub fn introspect_catalog(
+        &mut self,
+        client: &mut postgres::Client,
+    ) -> Result<&mut Self, Error> {
+        block_on(self.read_pg_catalog(&mut Blocking(client)))?;
+        Ok(self)
+    }
+
+    /// Like [`introspect_catalog()`](Self::introspect_catalog), through an async client.
+    #[cfg(feature = "tokio-postgres")]
+    pub async fn introspect_catalog_async(
+        &mut self,
+        client: &(impl tokio_postgres::GenericClient + Sync),
+    ) -> Result<&mut Self, Error> {
+        self.read_pg_catalog(&mut Async(client)).await?;
+        Ok(self)
+    }
+
+    #[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+    pub(crate) async fn read_pg_catalog(&mut self, client: &mut impl Client) -> Result<(), Error> {
+        let table_name = self.name.clone();
+        let schema = self.schema.clone();
+        let overrides = &self.type_overrides;
+        let mut col_index: IndexMap<String, Column> = IndexMap::new();
+        // Types are classified the same way as `information_schema.columns.data_type`.
+        for row in client.query("SELECT a.attname, a.attnotnull OR (t.typtype = 'd' AND t.typnotnull), CASE WHEN t.typtype = 'd' THEN 'USER-DEFINED' WHEN t.typcategory = 'A' THEN 'ARRAY' WHEN tn.nspname = 'pg_catalog' THEN format_type(a.atttypid, NULL) ELSE 'USER-DEFINED' END, tn.nspname, t.typname, t.typtype, format_type(a.atttypid, a.atttypmod), CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END, a.attidentity, a.attgenerated, col_description(c.oid, a.attnum) FROM pg_attribute AS a JOIN pg_class AS c ON c.oid = a.attrelid JOIN pg_namespace AS n ON n.oid = c.relnamespace JOIN pg_type AS t ON t.oid = a.atttypid JOIN pg_namespace AS tn ON tn.oid = t.typnamespace LEFT JOIN pg_attrdef AS d ON d.adrelid = a.attrelid AND d.adnum = a.attnum WHERE n.nspname = $1 AND c.relname = $2 AND a.attnum > 0 AND NOT a.attisdropped ORDER BY a.attnum;", &[&schema, &table_name]).await? {
+        let column_name: &str = row.get(0);
+        let not_null: bool = row.get(1);
+        let type_name: &str = row.get(4);
+        let domain_name = (row.get::<_, i8>(5) as u8 == b'd').then_some(type_name);
+        let mut r#type = match overrides.column(&schema, &table_name, column_name) {
+            Some(r#type) => with_replaced(r#type, client, row.get(2), row.get(3), type_name).await,
+            None => resolve_type(client, overrides, row.get(2), row.get(3), type_name)
+                .await
+                .map_err(|err| err.in_column(&table_name, column_name))?,
+        };
+        r#type.set_time_crate(self.time_crate).set_domain_mode(self.domain_mode);
+        let col = Column::new(column_name.to_string().into(), r#type)
+            .set_null(!not_null)
+            .set_domain(domain_name.map(|name| name.to_string().into()))
+            .set_type_def(Some(row.get::<_, String>(6).into()))
+            .set_default(row.get::<_, Option<String>>(7).map(|default| default.into()))
+            .set_identity(match row.get::<_, i8>(8) as u8 {
+                b'a' => Some(Identity::Always),
+                b'd' => Some(Identity::ByDefault),
+                _ => None,
+            })
+            .set_generated(row.get::<_, i8>(9) as u8 == b's')
+            .set_comment(row.get::<_, Option<String>>(10).map(|comment| comment.into()));
+        col_index.insert(column_name.to_string(), col);
+    }
+
+        let (mut primary_key, mut uniques, mut foreign_keys, mut checks) =
+            (None, vec![], vec![], vec![]);
+        for row in client.query("SELECT con.conname, con.contype, ARRAY(SELECT a.attname FROM unnest(con.conkey) WITH ORDINALITY AS k(attnum, i) JOIN pg_attribute AS a ON a.attrelid = con.conrelid AND a.attnum = k.attnum ORDER BY k.i)::text[], fc.relname, ARRAY(SELECT a.attname FROM unnest(con.confkey) WITH ORDINALITY AS k(attnum, i) JOIN pg_attribute AS a ON a.attrelid = con.confrelid AND a.attnum = k.attnum ORDER BY k.i)::text[], pg_get_constraintdef(con.oid), fn.nspname FROM pg_constraint AS con JOIN pg_class AS c ON c.oid = con.conrelid JOIN pg_namespace AS n ON n.oid = c.relnamespace LEFT JOIN pg_class AS fc ON fc.oid = con.confrelid LEFT JOIN pg_namespace AS fn ON fn.oid = fc.relnamespace WHERE n.nspname = $1 AND c.relname = $2 ORDER BY con.conname", &[&schema, &table_name]).await? {
+        let name: String = row.get(0);
+        let contype = row.get::<_, i8>(1) as u8;
+        let columns: Vec<String> = row.get(2);
+        let ref_table: Option<String> = row.get(3);
+        let ref_schema: Option<String> = row.get(6);
+        let ref_columns: Vec<Cow<'static, str>> = row.get::<_, Vec<String>>(4).into_iter().map(Cow::from).collect();
+        for column in &columns {
+            let col = col_index.get_mut(column).ok_or_else(|| Error::UnknownColumn {
+                table: table_name.clone(),
+                name: name.clone().into(),
+                column: column.clone().into(),
+            })?;
+            match (contype, &ref_table) {
+                (b'p', _) => col.primary_key = true,
+                (b'u', _) => col.unique = true,
+                (b'f', Some(ref_table)) if col.foreign_key.is_none() => {
+                    col.foreign_key = Some(
+                        ForeignKey::new(ref_table.clone().into(), ref_columns.clone())
+                            .set_to_schema(ref_schema.clone().unwrap_or_default().into()),
+                    );
+                }
+                _ => {}
+            }
+        }
+        match (contype, ref_table) {
+            (b'p', _) => primary_key = Some(Constraint::PrimaryKey {
+                name: name.into(),
+                columns: columns.into_iter().map(Cow::from).collect(),
+            }),
+            (b'u', _) => uniques.push(Constraint::Unique {
+                name: name.into(),
+                columns: columns.into_iter().map(Cow::from).collect(),
+            }),
+            (b'f', Some(ref_table)) => foreign_keys.push(Constraint::ForeignKey {
+                name: name.into(),
+                columns: columns.into_iter().map(Cow::from).collect::<Vec<_>>().into(),
+                ref_schema: ref_schema.unwrap_or_default().into(),
+                ref_table: ref_table.into(),
+                ref_columns: ref_columns.into(),
+            }),
+            (b'c', _) => checks.push(Constraint::Check {
+                name: name.into(),
+                expression: check::expression(row.get(5)).to_string().into(),
+            }),
+            // Exclusion constraints and constraint triggers don't affect the generated code.
+            _ => {}
         }
     }
+        self.constraints.extend(primary_key);
+        self.constraints.extend(uniques);
+        self.read_unique_indexes(client, &mut col_index).await?;
+        self.constraints.extend(foreign_keys);
+        self.constraints.extend(checks);
 
         for (_, col) in col_index.into_iter() {
-            struct_bldr.add_column(col);
+            self.add_column(col);
         }
 
-        Ok(struct_bldr)
+        Ok(())
+    }
+}
+
+/// Resolve a column type from its `information_schema` `data_type` and `udt_name`.
+///
+/// Boxed since array and domain types recurse into their element or base type.
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+fn resolve_type<'a>(
+    client: &'a mut impl Client,
+    overrides: &'a TypeOverrides,
+    data_type: &'a str,
+    udt_schema: &'a str,
+    udt_name: &'a str,
+) -> BoxFuture<'a, Result<Type, Error>> {
+    Box::pin(async move {
+        if let Some(r#type) = overrides
+            .r#type(udt_name)
+            .or_else(|| overrides.r#type(data_type))
+        {
+            return Ok(with_replaced(r#type, client, data_type, udt_schema, udt_name).await);
+        }
+        match data_type {
+            "USER-DEFINED" => user_defined_type(client, overrides, udt_schema, udt_name).await,
+            "ARRAY" => {
+                let row = client.query_one("SELECT CASE WHEN e.typtype IN ('e', 'c', 'd') THEN 'USER-DEFINED' ELSE format_type(e.oid, NULL) END, en.nspname, e.typname FROM pg_type AS t JOIN pg_namespace AS n ON n.oid = t.typnamespace JOIN pg_type AS e ON e.oid = t.typelem JOIN pg_namespace AS en ON en.oid = e.typnamespace WHERE n.nspname = $1 AND t.typname = $2;", &[&udt_schema, &udt_name]).await?;
+                Ok(Type::Array {
+                    inner: Box::new(
+                        resolve_type(client, overrides, row.get(0), row.get(1), row.get(2)).await?,
+                    ),
+                })
+            }
+            _ => Type::from_str(data_type),
+        }
+    })
+}
+
+/// Fill in the type an override replaces, resolved without any overrides. It is left out
+/// if it isn't supported, as only [`Backend::Diesel`] needs it.
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+async fn with_replaced(
+    r#type: Type,
+    client: &mut impl Client,
+    data_type: &str,
+    udt_schema: &str,
+    udt_name: &str,
+) -> Type {
+    match r#type {
+        Type::Custom {
+            path,
+            replaces: None,
+        } => {
+            let overrides = TypeOverrides::new();
+            let replaces = resolve_type(client, &overrides, data_type, udt_schema, udt_name)
+                .await
+                .ok()
+                .map(Box::new);
+            Type::Custom { path, replaces }
+        }
+        r#type => r#type,
     }
 }
 
+/// Resolve a `USER-DEFINED` column type by looking it up in `pg_type`.
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+fn user_defined_type<'a>(
+    client: &'a mut impl Client,
+    overrides: &'a TypeOverrides,
+    schema: &'a str,
+    name: &'a str,
+) -> BoxFuture<'a, Result<Type, Error>> {
+    Box::pin(async move {
+        let row = client.query_one("SELECT t.typtype FROM pg_type AS t JOIN pg_namespace AS n ON n.oid = t.typnamespace WHERE n.nspname = $1 AND t.typname = $2;", &[&schema, &name]).await?;
+        let typtype: i8 = row.get(0);
+        match typtype as u8 {
+            b'e' => {
+                let variants = client
+                .query("SELECT e.enumlabel FROM pg_enum AS e JOIN pg_type AS t ON t.oid = e.enumtypid JOIN pg_namespace AS n ON n.oid = t.typnamespace WHERE n.nspname = $1 AND t.typname = $2 ORDER BY e.enumsortorder;", &[&schema, &name]).await?
+                .into_iter()
+                .map(|row| row.get::<_, String>(0).into())
+                .collect();
+                Ok(Type::Enum {
+                    name: name.to_string().into(),
+                    schema: schema.to_string().into(),
+                    variants,
+                })
+            }
+            b'c' => {
+                let mut inner = StructBuilder::new(name.to_string().into());
+                inner
+                    .set_schema(schema.to_string().into())
+                    .set_type_overrides(overrides.clone());
+                for row in client.query("SELECT a.attname, CASE WHEN t.typtype IN ('e', 'c', 'd') THEN 'USER-DEFINED' WHEN t.typcategory = 'A' THEN 'ARRAY' ELSE format_type(a.atttypid, NULL) END, tn.nspname, t.typname FROM pg_attribute AS a JOIN pg_type AS ct ON ct.typrelid = a.attrelid JOIN pg_namespace AS cn ON cn.oid = ct.typnamespace JOIN pg_type AS t ON t.oid = a.atttypid JOIN pg_namespace AS tn ON tn.oid = t.typnamespace WHERE cn.nspname = $1 AND ct.typname = $2 AND a.attnum > 0 AND NOT a.attisdropped ORDER BY a.attnum;", &[&schema, &name]).await? {
+                let attribute_name: &str = row.get(0);
+                let data_type: &str = row.get(1);
+                let r#type = match overrides.column(schema, name, attribute_name) {
+                    Some(r#type) => with_replaced(r#type, client, data_type, row.get(2), row.get(3)).await,
+                    None => resolve_type(client, overrides, data_type, row.get(2), row.get(3))
+                        .await
+                        .map_err(|err| err.in_column(name, attribute_name))?,
+                };
+                // Attributes of a composite type can always be NULL.
+                inner.add_column(Column::new(attribute_name.to_string().into(), r#type).set_null(true));
+            }
+                Ok(Type::Composite { inner })
+            }
+            b'd' => {
+                let row = client.query_one("SELECT CASE WHEN b.typtype IN ('e', 'c', 'd') THEN 'USER-DEFINED' WHEN b.typcategory = 'A' THEN 'ARRAY' ELSE format_type(b.oid, NULL) END, bn.nspname, b.typname FROM pg_type AS t JOIN pg_namespace AS n ON n.oid = t.typnamespace JOIN pg_type AS b ON b.oid = t.typbasetype JOIN pg_namespace AS bn ON bn.oid = b.typnamespace WHERE n.nspname = $1 AND t.typname = $2;", &[&schema, &name]).await?;
+                Ok(Type::Domain {
+                    name: name.to_string().into(),
+                    schema: schema.to_string().into(),
+                    inner: Box::new(
+                        resolve_type(client, overrides, row.get(0), row.get(1), row.get(2)).await?,
+                    ),
+                    mode: DomainMode::default(),
+                })
+            }
+            _ => Err(Error::UnknownType {
+                pg_type: format!("{schema}.{name}").into(),
+            }),
+        }
+    })
+}
+
 impl std::fmt::Display for StructBuilder {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
         let columns = self.columns.values().fold(String::new(), |mut acc, col| {
+            for line in col.comment.iter().flat_map(|c| c.lines()) {
+                acc.push_str(&format!("    /// {}\n", line).replace("/// \n", "///\n"));
+            }
+            // `FromRow` looks up each field by name.
+            if self.backend == Backend::Sqlx && AsSnakeCase(&col.name).to_string() != col.name {
+                acc.push_str(&format!("    #[sqlx(rename = \"{}\")]\n", col.name));
+            }
+            acc.push_str(&self.build_field_attributes(col));
             acc.push_str(&format!("    pub {},\n", col));
             acc
         });
         write!(
             fmt,
-            r#"pub struct {} {{
+            r#"{}pub struct {} {{
 {}}}
         "#,
+            match self.backend {
+                Backend::Sqlx => self.build_struct_attributes(&["sqlx::FromRow"], ""),
+                Backend::Diesel => self.build_struct_attributes(
+                    &["diesel::Queryable", "diesel::Selectable"],
+                    &format!(
+                        "#[diesel(table_name = {})]\n#[diesel(check_for_backend(diesel::pg::Pg))]\n",
+                        AsSnakeCase(&self.name)
+                    ),
+                ),
+                _ => self.build_struct_attributes(&[], ""),
+            },
             AsUpperCamelCase(&self.name),
             columns
         )
     }
 }
+
+/// The conflict target of `upsert` and the columns it updates on a conflict.
+type ConflictColumns<'a> = (Vec<&'a Column>, Vec<&'a Column>);
+
+/// Whether `col` is part of the `New` struct. Keys Postgres numbers, like `serial` ones,
+/// are left out, other keys are inserted like any column, defaulted ones optionally.
+fn is_inserted(col: &Column) -> bool {
+    !(col.is_generated() || col.primary_key && col.is_serial())
+}
+
+/// A cast for the parameter of `col` in an INSERT, if it needs one.
+fn cast(col: &Column) -> String {
+    // Parameters for domains generated as their base type must be cast to it.
+    match col.r#type.pg_name().filter(|_| col.r#type.needs_cast()) {
+        Some(pg_name) => format!("::{pg_name}"),
+        None => String::new(),
+    }
+}
+
+/// The parameters of a method taking the values of `key`.
+fn key_params(key: &[&Column]) -> String {
+    key.iter()
+        .map(|col| format!("{}: {}", AsSnakeCase(&col.name), param_type(col)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The type of a parameter taking a value of `col`.
+fn param_type(col: &Column) -> String {
+    TypeAsRef {
+        lifetime: None,
+        val: &col.r#type,
+    }
+    .to_string()
+}
+
+/// A condition comparing the `key` columns to the first parameters of the query.
+fn key_condition(key: &[&Column]) -> String {
+    key.iter()
+        .enumerate()
+        .map(|(i, col)| format!("{} = ${}{}", quote_ident(&col.name), i + 1, cast(col)))
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+/// Calls binding `values` to a sqlx query.
+fn binds(values: &[String]) -> String {
+    values.iter().fold(String::new(), |acc, value| {
+        acc + "\n                .bind(" + value + ")"
+    })
+}
+
+/// A cast for an array parameter of values of `col`, if it needs one.
+fn array_cast(col: &Column) -> String {
+    match col.r#type.pg_name().filter(|_| col.r#type.needs_cast()) {
+        Some(pg_name) => format!("::{pg_name}[]"),
+        None => String::new(),
+    }
+}
+
+/// `col` in a select list, cast to the type its field decodes if needed.
+fn select(col: &Column) -> String {
+    let name = quote_ident(&col.name);
+    // Arrays of domains generated as their base type are described as arrays of the domain,
+    // which the base type does not accept. Columns of the domain itself are described by
+    // their base type already.
+    match (&col.r#type, col.r#type.pg_name()) {
+        (Type::Array { .. }, Some(pg_name)) if col.r#type.needs_cast() => {
+            format!("{name}::{pg_name} AS {name}")
+        }
+        _ => name.into_owned(),
+    }
+}
+
+/// Quote `ident` for use in SQL if it isn't a lowercase identifier or is a reserved keyword.
+pub(crate) fn quote_ident(ident: &str) -> Cow<'_, str> {
+    let plain = ident.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && ident
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$');
+    match plain && !RESERVED_KEYWORDS.contains(&ident) {
+        true => Cow::Borrowed(ident),
+        false => Cow::Owned(format!("\"{}\"", ident.replace('"', "\"\""))),
+    }
+}
+
+/// Keywords that can't be used as table or column names without quoting them.
+#[rustfmt::skip]
+const RESERVED_KEYWORDS: &[&str] = &[
+    "all", "analyse", "analyze", "and", "any", "array", "as", "asc", "asymmetric", "authorization",
+    "binary", "both", "case", "cast", "check", "collate", "collation", "column", "concurrently",
+    "constraint", "create", "cross", "current_catalog", "current_date", "current_role",
+    "current_schema", "current_time", "current_timestamp", "current_user", "default", "deferrable",
+    "desc", "distinct", "do", "else", "end", "except", "false", "fetch", "for", "foreign", "freeze",
+    "from", "full", "grant", "group", "having", "ilike", "in", "initially", "inner", "intersect",
+    "into", "is", "isnull", "join", "lateral", "leading", "left", "like", "limit", "localtime",
+    "localtimestamp", "natural", "not", "notnull", "null", "offset", "on", "only", "or", "order",
+    "outer", "overlaps", "placing", "primary", "references", "returning", "right", "select",
+    "session_user", "similar", "some", "symmetric", "system_user", "table", "tablesample", "then",
+    "to", "trailing", "true", "union", "unique", "user", "using", "variadic", "verbose", "when",
+    "where", "window", "with",
+];
@@ -1,67 +1,375 @@
-use crate::StructBuilder;
-use heck::AsUpperCamelCase;
+use crate::struct_builder::quote_ident;
+use crate::{Error, StructBuilder};
+use heck::{AsSnakeCase, AsUpperCamelCase};
+use indexmap::IndexMap;
 use postgres_types::Type as PgType;
+use std::borrow::Cow;
 use std::str::FromStr;
 
 #[derive(Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Type {
-    Builtin { inner: PgType },
-    Composite { inner: StructBuilder },
+    Builtin {
+        inner: PgType,
+    },
+    DateTime {
+        inner: PgType,
+        time_crate: TimeCrate,
+    },
+    Composite {
+        inner: StructBuilder,
+    },
+    Enum {
+        name: Cow<'static, str>,
+        /// The schema containing the type.
+        schema: Cow<'static, str>,
+        variants: Vec<Cow<'static, str>>,
+    },
+    Array {
+        inner: Box<Type>,
+    },
+    /// A `CREATE DOMAIN` type, generated as `inner` or as a newtype around it.
+    Domain {
+        name: Cow<'static, str>,
+        /// The schema containing the type.
+        schema: Cow<'static, str>,
+        inner: Box<Type>,
+        mode: DomainMode,
+    },
+    /// A Rust type supplied through [`TypeOverrides`](crate::TypeOverrides).
+    Custom {
+        path: Cow<'static, str>,
+        /// The type of the column without the override, if it is supported. diesel needs
+        /// it for the SQL type of the column.
+        replaces: Option<Box<Type>>,
+    },
+}
+
+/// The crate whose types represent `timestamp`, `date` and `time` columns.
+///
+/// Range types always use `chrono`, as that is all `postgres_range` supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeCrate {
+    #[default]
+    Chrono,
+    Time,
+    Jiff,
+}
+
+impl TimeCrate {
+    fn type_path(&self, inner: &PgType) -> Option<&'static str> {
+        Some(match (self, inner) {
+            (Self::Chrono, &PgType::TIMESTAMP) => "chrono::naive::NaiveDateTime",
+            (Self::Chrono, &PgType::TIMESTAMPTZ) => "chrono::DateTime<chrono::Utc>",
+            (Self::Chrono, &PgType::DATE) => "chrono::NaiveDate",
+            (Self::Chrono, &PgType::TIME) => "chrono::NaiveTime",
+            (Self::Time, &PgType::TIMESTAMP) => "time::PrimitiveDateTime",
+            (Self::Time, &PgType::TIMESTAMPTZ) => "time::OffsetDateTime",
+            (Self::Time, &PgType::DATE) => "time::Date",
+            (Self::Time, &PgType::TIME) => "time::Time",
+            (Self::Jiff, &PgType::TIMESTAMP) => "jiff::civil::DateTime",
+            (Self::Jiff, &PgType::TIMESTAMPTZ) => "jiff::Timestamp",
+            (Self::Jiff, &PgType::DATE) => "jiff::civil::Date",
+            (Self::Jiff, &PgType::TIME) => "jiff::civil::Time",
+            _ => return None,
+        })
+    }
+}
+
+impl FromStr for TimeCrate {
+    type Err = Error;
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "chrono" => Ok(Self::Chrono),
+            "time" => Ok(Self::Time),
+            "jiff" => Ok(Self::Jiff),
+            _ => Err(Error::InvalidValue {
+                value: val.to_string().into(),
+                expected: "`chrono`, `time` or `jiff`",
+            }),
+        }
+    }
+}
+
+/// How columns whose type is a Postgres domain are generated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DomainMode {
+    /// Use the Rust type of the domain's base type.
+    #[default]
+    BaseType,
+    /// Emit a newtype around the base type for every domain.
+    Newtype,
+}
+
+impl FromStr for DomainMode {
+    type Err = Error;
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "base-type" => Ok(Self::BaseType),
+            "newtype" => Ok(Self::Newtype),
+            _ => Err(Error::InvalidValue {
+                value: val.to_string().into(),
+                expected: "`base-type` or `newtype`",
+            }),
+        }
+    }
+}
+
+/// The client library the generated methods are written for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Blocking methods taking a `postgres::Client`.
+    #[default]
+    Postgres,
+    /// `async` methods taking any `tokio_postgres::GenericClient`.
+    TokioPostgres,
+    /// `async` methods taking a `sqlx::PgPool` or `sqlx::PgConnection`, with
+    /// `sqlx::FromRow` and `sqlx::Type` derived instead of `FromSql`/`ToSql`.
+    Sqlx,
+    /// Blocking methods taking a `diesel::PgConnection`, with `Queryable` and `Insertable`
+    /// derived for the tables of [`StructBuilder::build_diesel_schema()`].
+    Diesel,
+}
+
+impl Backend {
+    /// The path of the client crate in generated code.
+    pub(crate) fn crate_path(&self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::TokioPostgres => "tokio_postgres",
+            Self::Sqlx => "sqlx",
+            Self::Diesel => "diesel",
+        }
+    }
+
+    /// The error type returned by the generated methods.
+    pub(crate) fn error_path(&self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres::Error",
+            Self::TokioPostgres => "tokio_postgres::Error",
+            Self::Sqlx => "sqlx::Error",
+            Self::Diesel => "diesel::result::Error",
+        }
+    }
+
+    /// What precedes `fn` in the generated methods.
+    pub(crate) fn asyncness(&self) -> &'static str {
+        match self {
+            Self::Postgres | Self::Diesel => "",
+            Self::TokioPostgres | Self::Sqlx => "async ",
+        }
+    }
+
+    /// What follows a call to the client before `?`.
+    pub(crate) fn await_suffix(&self) -> &'static str {
+        match self {
+            Self::Postgres | Self::Diesel => "",
+            Self::TokioPostgres | Self::Sqlx => ".await",
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = Error;
+    fn from_str(val: &str) -> Result<Self, Self::Err> {
+        match val {
+            "postgres" => Ok(Self::Postgres),
+            "tokio-postgres" => Ok(Self::TokioPostgres),
+            "sqlx" => Ok(Self::Sqlx),
+            "diesel" => Ok(Self::Diesel),
+            _ => Err(Error::InvalidValue {
+                value: val.to_string().into(),
+                expected: "`postgres`, `tokio-postgres`, `sqlx` or `diesel`",
+            }),
+        }
+    }
 }
 
 impl FromStr for Type {
-    type Err = anyhow::Error;
+    type Err = Error;
     fn from_str(val: &str) -> Result<Self, Self::Err> {
+        if let Some(element) = val.strip_suffix("[]") {
+            return Ok(Self::Array {
+                inner: Box::new(Self::from_str(element)?),
+            });
+        }
+        let date_time = match val {
+            "timestamp with time zone" => Some(PgType::TIMESTAMPTZ),
+            "timestamp without time zone" => Some(PgType::TIMESTAMP),
+            "date" => Some(PgType::DATE),
+            "time without time zone" => Some(PgType::TIME),
+            _ => None,
+        };
+        if let Some(inner) = date_time {
+            return Ok(Self::DateTime {
+                inner,
+                time_crate: TimeCrate::default(),
+            });
+        }
         Ok(Self::Builtin {
             inner: match val {
-                "bigint" => PgType::INT8,
+                "\"char\"" => PgType::CHAR,
+                "smallint" => PgType::INT2,
                 "integer" => PgType::INT4,
+                "bigint" => PgType::INT8,
+                "oid" => PgType::OID,
+                "real" => PgType::FLOAT4,
+                "double precision" => PgType::FLOAT8,
+                "numeric" => PgType::NUMERIC,
+                "money" => PgType::MONEY,
                 "text" => PgType::TEXT,
-                "text[]" => PgType::TEXT_ARRAY,
                 "bytea" => PgType::BYTEA,
-                "bytea[]" => PgType::BYTEA_ARRAY,
                 "boolean" => PgType::BOOL,
                 "character varying" => PgType::TEXT,
-                "timestamp with time zone" => PgType::TIMESTAMPTZ,
-                "timestamp without time zone" => PgType::TIMESTAMP,
-                _ => todo!(),
+                "interval" => PgType::INTERVAL,
+                "inet" => PgType::INET,
+                "cidr" => PgType::CIDR,
+                "macaddr" => PgType::MACADDR,
+                "int4range" => PgType::INT4_RANGE,
+                "int8range" => PgType::INT8_RANGE,
+                "tsrange" => PgType::TS_RANGE,
+                "tstzrange" => PgType::TSTZ_RANGE,
+                // No Rust type implements `FromSql`/`ToSql` for these.
+                "time with time zone" | "macaddr8" | "numrange" | "daterange" => {
+                    return Err(Error::UnknownType {
+                        pg_type: val.to_string().into(),
+                    })
+                }
+                #[cfg(feature = "uuid")]
+                "uuid" => PgType::UUID,
+                #[cfg(not(feature = "uuid"))]
+                "uuid" => {
+                    return Err(Error::MissingFeature {
+                        pg_type: val.to_string().into(),
+                        feature: "uuid",
+                    })
+                }
+                #[cfg(feature = "serde_json")]
+                "json" => PgType::JSON,
+                #[cfg(feature = "serde_json")]
+                "jsonb" => PgType::JSONB,
+                #[cfg(not(feature = "serde_json"))]
+                "json" | "jsonb" => {
+                    return Err(Error::MissingFeature {
+                        pg_type: val.to_string().into(),
+                        feature: "serde_json",
+                    })
+                }
+                _ => {
+                    return Err(Error::UnknownType {
+                        pg_type: val.to_string().into(),
+                    })
+                }
             },
         })
     }
 }
 
-impl std::fmt::Display for Type {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
+impl Type {
+    /// The Rust type of values of this type, [`Error::UnknownType`] if there is none.
+    pub fn rust_type(&self) -> Result<String, Error> {
+        Ok(match self {
             Self::Builtin {
-                inner: PgType::INT8,
-            } => write!(fmt, "i64"),
+                inner: PgType::CHAR,
+            } => "i8".to_string(),
+            Self::Builtin {
+                inner: PgType::INT2,
+            } => "i16".to_string(),
             Self::Builtin {
                 inner: PgType::INT4,
-            } => write!(fmt, "i32"),
+            } => "i32".to_string(),
             Self::Builtin {
-                inner: PgType::TEXT,
-            } => write!(fmt, "String"),
+                inner: PgType::INT8,
+            } => "i64".to_string(),
+            Self::Builtin { inner: PgType::OID } => "u32".to_string(),
             Self::Builtin {
-                inner: PgType::TEXT_ARRAY,
-            } => write!(fmt, "Vec<String>"),
+                inner: PgType::FLOAT4,
+            } => "f32".to_string(),
             Self::Builtin {
-                inner: PgType::BYTEA,
-            } => write!(fmt, "Vec<u8>"),
+                inner: PgType::FLOAT8,
+            } => "f64".to_string(),
+            Self::Builtin {
+                inner: PgType::NUMERIC,
+            } => "rust_decimal::Decimal".to_string(),
+            Self::Builtin {
+                inner: PgType::MONEY,
+            } => "postgres_money::Money".to_string(),
             Self::Builtin {
-                inner: PgType::BYTEA_ARRAY,
-            } => write!(fmt, "Vec<Vec<u8>>"),
+                inner: PgType::TEXT,
+            } => "String".to_string(),
+            Self::Builtin {
+                inner: PgType::BYTEA,
+            } => "Vec<u8>".to_string(),
             Self::Builtin {
                 inner: PgType::BOOL,
-            } => write!(fmt, "bool"),
+            } => "bool".to_string(),
+            Self::Builtin {
+                inner: PgType::INTERVAL,
+            } => "pg_interval::Interval".to_string(),
+            Self::Builtin {
+                inner: PgType::INET,
+            } => "std::net::IpAddr".to_string(),
+            Self::Builtin {
+                inner: PgType::CIDR,
+            } => "cidr::IpCidr".to_string(),
+            Self::Builtin {
+                inner: PgType::MACADDR,
+            } => "eui48::MacAddress".to_string(),
+            Self::Builtin {
+                inner: PgType::INT4_RANGE,
+            } => "postgres_range::Range<i32>".to_string(),
+            Self::Builtin {
+                inner: PgType::INT8_RANGE,
+            } => "postgres_range::Range<i64>".to_string(),
+            Self::Builtin {
+                inner: PgType::TS_RANGE,
+            } => "postgres_range::Range<chrono::naive::NaiveDateTime>".to_string(),
             Self::Builtin {
-                inner: PgType::TIMESTAMP,
-            } => write!(fmt, "chrono::naive::NaiveDateTime"),
+                inner: PgType::TSTZ_RANGE,
+            } => "postgres_range::Range<chrono::DateTime<chrono::Utc>>".to_string(),
             Self::Builtin {
-                inner: PgType::TIMESTAMPTZ,
-            } => write!(fmt, "chrono::DateTime<chrono::Utc>"),
-            Self::Composite { inner } => write!(fmt, "{}", AsUpperCamelCase(&inner.name)),
-            _ => todo!(),
+                inner: PgType::UUID,
+            } => "uuid::Uuid".to_string(),
+            Self::Builtin {
+                inner: PgType::JSON,
+            }
+            | Self::Builtin {
+                inner: PgType::JSONB,
+            } => "serde_json::Value".to_string(),
+            Self::Composite { inner } => AsUpperCamelCase(&inner.name).to_string(),
+            Self::DateTime { inner, time_crate } => match time_crate.type_path(inner) {
+                Some(path) => path.to_string(),
+                None => return Err(self.unknown()),
+            },
+            Self::Enum { name, .. } => AsUpperCamelCase(name).to_string(),
+            Self::Array { inner } => format!("Vec<{}>", inner.rust_type()?),
+            Self::Custom { path, .. } => path.to_string(),
+            Self::Domain {
+                name,
+                mode: DomainMode::Newtype,
+                ..
+            } => AsUpperCamelCase(name).to_string(),
+            Self::Domain { inner, .. } => inner.rust_type()?,
+            _ => return Err(self.unknown()),
+        })
+    }
+
+    /// [`Error::UnknownType`] for this type.
+    fn unknown(&self) -> Error {
+        Error::UnknownType {
+            pg_type: self.pg_name().unwrap_or_default().into(),
+        }
+    }
+}
+
+/// Writes the Rust type of values of this type. A type that has none, as reported by
+/// [`Type::unsupported()`], is written as the error in angle brackets, like
+/// ``<unsupported type `_int4range`>``, which never passes for Rust code.
+impl std::fmt::Display for Type {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.rust_type() {
+            Ok(rust_type) => fmt.write_str(&rust_type),
+            Err(err) => write!(fmt, "<{err}>"),
         }
     }
 }
@@ -73,33 +381,618 @@ impl Type {
                 inner: PgType::BOOL,
             }
             | Self::Builtin {
-                inner: PgType::TIMESTAMP,
+                inner: PgType::CHAR,
+            }
+            | Self::Builtin {
+                inner: PgType::INT2,
             }
             | Self::Builtin {
-                inner: PgType::TIMESTAMPTZ,
+                inner: PgType::INT4,
             }
             | Self::Builtin {
                 inner: PgType::INT8,
             }
+            | Self::Builtin { inner: PgType::OID }
             | Self::Builtin {
-                inner: PgType::INT4,
-            } => true,
+                inner: PgType::FLOAT4,
+            }
+            | Self::Builtin {
+                inner: PgType::FLOAT8,
+            }
+            | Self::Builtin {
+                inner: PgType::NUMERIC,
+            }
+            | Self::Builtin {
+                inner: PgType::MONEY,
+            }
+            | Self::Builtin {
+                inner: PgType::UUID,
+            }
+            | Self::Builtin {
+                inner: PgType::INTERVAL,
+            }
+            | Self::Builtin {
+                inner: PgType::INET,
+            }
+            | Self::Builtin {
+                inner: PgType::CIDR,
+            }
+            | Self::Builtin {
+                inner: PgType::MACADDR,
+            }
+            | Self::Builtin {
+                inner: PgType::INT4_RANGE,
+            }
+            | Self::Builtin {
+                inner: PgType::INT8_RANGE,
+            }
+            | Self::Builtin {
+                inner: PgType::TS_RANGE,
+            }
+            | Self::Builtin {
+                inner: PgType::TSTZ_RANGE,
+            }
+            | Self::DateTime { .. }
+            | Self::Enum { .. } => true,
             Self::Builtin {
                 inner: PgType::TEXT,
             }
             | Self::Builtin {
-                inner: PgType::TEXT_ARRAY,
+                inner: PgType::BYTEA,
             }
             | Self::Builtin {
-                inner: PgType::BYTEA,
+                inner: PgType::JSON,
             }
             | Self::Builtin {
-                inner: PgType::BYTEA_ARRAY,
+                inner: PgType::JSONB,
+            }
+            | Self::Composite { inner: _ }
+            | Self::Array { inner: _ }
+            | Self::Custom { .. } => false,
+            Self::Domain { inner, .. } => inner.is_copy(),
+            _ => false,
+        }
+    }
+
+    /// Use `time_crate` for all date and time types, including nested ones.
+    pub fn set_time_crate(&mut self, time_crate: TimeCrate) -> &mut Self {
+        match self {
+            Self::DateTime {
+                time_crate: current,
+                ..
+            } => *current = time_crate,
+            Self::Composite { inner } => {
+                inner.set_time_crate(time_crate);
+            }
+            Self::Array { inner } | Self::Domain { inner, .. } => {
+                inner.set_time_crate(time_crate);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Generate domains, including nested ones, according to `mode`.
+    pub fn set_domain_mode(&mut self, mode: DomainMode) -> &mut Self {
+        match self {
+            Self::Domain {
+                inner,
+                mode: current,
+                ..
+            } => {
+                *current = mode;
+                inner.set_domain_mode(mode);
+            }
+            Self::Composite { inner } => {
+                inner.set_domain_mode(mode);
+            }
+            Self::Array { inner } => {
+                inner.set_domain_mode(mode);
+            }
+            _ => {}
+        }
+        self
+    }
+
+    /// Name of the Postgres type the generated Rust type maps to, `None` for [`Type::Custom`].
+    pub fn pg_name(&self) -> Option<String> {
+        match self {
+            Self::Domain {
+                inner,
+                mode: DomainMode::BaseType,
+                ..
+            } => inner.pg_name(),
+            Self::Builtin { inner } | Self::DateTime { inner, .. } => {
+                Some(inner.name().to_string())
+            }
+            Self::Composite { inner } => Some(inner.name.to_string()),
+            Self::Enum { name, .. } | Self::Domain { name, .. } => Some(name.to_string()),
+            Self::Array { inner } => inner.pg_name().map(|name| format!("{name}[]")),
+            Self::Custom { .. } => None,
+        }
+    }
+
+    /// The first type, this one or a nested one, that no Rust code can be generated for.
+    pub fn unsupported(&self) -> Option<&Type> {
+        let as_ref = TypeAsRef {
+            lifetime: None,
+            val: self,
+        };
+        let nested = match self {
+            Self::Composite { inner } => inner
+                .columns
+                .values()
+                .find_map(|col| col.r#type.unsupported()),
+            Self::Array { inner } | Self::Domain { inner, .. } => inner.unsupported(),
+            _ => None,
+        };
+        if nested.is_some() {
+            return nested;
+        }
+        if self.rust_type().is_err() || as_ref.rust_type().is_err() {
+            return Some(self);
+        }
+        None
+    }
+
+    /// [`Error::UnknownType`] for the first unsupported type within this one, if any.
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        match self.unsupported() {
+            Some(r#type) => Err(Error::UnknownType {
+                pg_type: r#type.pg_name().unwrap_or_default().into(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether parameters of this type must be cast to [`Type::pg_name`], because the Rust
+    /// type does not accept the Postgres type of the column.
+    pub(crate) fn needs_cast(&self) -> bool {
+        match self {
+            Self::Domain {
+                mode: DomainMode::BaseType,
+                ..
+            } => true,
+            Self::Array { inner } => inner.needs_cast(),
+            _ => false,
+        }
+    }
+
+    /// Rust definition of a user-defined type for `backend`, also deriving `extra_derives`
+    /// unless it already does. `None` for builtin types.
+    pub fn build_definition(
+        &self,
+        backend: Backend,
+        extra_derives: &[&str],
+    ) -> Result<Option<String>, Error> {
+        self.check()?;
+        let extra = |derived: &[&str]| {
+            extra_derives
+                .iter()
+                .filter(|derive| !derived.contains(&derive.rsplit("::").next().unwrap_or_default()))
+                .fold(String::new(), |acc, derive| acc + derive + ", ")
+        };
+        let derives = match backend {
+            Backend::Postgres | Backend::TokioPostgres => {
+                "postgres_types::ToSql, postgres_types::FromSql"
+            }
+            Backend::Sqlx => "sqlx::Type",
+            Backend::Diesel => "diesel::expression::AsExpression, diesel::deserialize::FromSqlRow",
+        };
+        // The attributes naming the Postgres type, and a field or variant of it. sqlx looks
+        // the type up by name, so it must be qualified unless it is on the `search_path`.
+        let type_name = |schema: &str, name: &str| match backend {
+            Backend::Postgres | Backend::TokioPostgres => format!("#[postgres(name = {name:?})]"),
+            Backend::Sqlx => format!(
+                "#[sqlx(type_name = {:?})]",
+                format!("{}.{}", quote_ident(schema), quote_ident(name))
+            ),
+            Backend::Diesel => format!(
+                "#[diesel(sql_type = {})]",
+                self.diesel_sql_type(true).unwrap_or_default()
+            ),
+        };
+        let rename = |name: &str| match backend {
+            Backend::Postgres | Backend::TokioPostgres => {
+                Some(format!("#[postgres(name = {name:?})]"))
+            }
+            Backend::Sqlx => Some(format!("#[sqlx(rename = {name:?})]")),
+            // The `ToSql`/`FromSql` implementations spell out every variant.
+            Backend::Diesel => None,
+        };
+        let definition = match self {
+            Self::Composite { inner } => {
+                let fields = inner.columns.values().fold(String::new(), |mut acc, col| {
+                    // sqlx and diesel encode and decode composites by position.
+                    if backend != Backend::Sqlx && AsSnakeCase(&col.name).to_string() != col.name {
+                        if let Some(rename) = rename(&col.name) {
+                            acc.push_str(&format!("    {}\n", rename));
+                        }
+                    }
+                    acc.push_str(&format!("    pub {},\n", col));
+                    acc
+                });
+                format!(
+                    r#"#[derive(Debug, Clone, PartialEq, {}{derives})]
+{}
+pub struct {} {{
+{}}}
+        "#,
+                    extra(&["Debug", "Clone", "PartialEq"]),
+                    type_name(&inner.schema, &inner.name),
+                    AsUpperCamelCase(&inner.name),
+                    fields
+                )
+            }
+            Self::Enum {
+                name,
+                schema,
+                variants,
+            } => {
+                let names = variant_names(name, variants)?;
+                let variants = variants.iter().zip(names).fold(
+                    String::new(),
+                    |mut acc, (variant, variant_name)| {
+                        if let Some(rename) = rename(variant) {
+                            acc.push_str(&format!("    {}\n", rename));
+                        }
+                        acc.push_str(&format!("    {},\n", variant_name));
+                        acc
+                    },
+                );
+                format!(
+                    r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, {}{derives})]
+{}
+pub enum {} {{
+{}}}
+        "#,
+                    extra(&["Debug", "Clone", "Copy", "PartialEq", "Eq"]),
+                    type_name(schema, name),
+                    AsUpperCamelCase(name),
+                    variants
+                )
+            }
+            Self::Domain {
+                name,
+                inner,
+                mode: DomainMode::Newtype,
+                ..
+            } => format!(
+                r#"#[derive(Debug, Clone, {}PartialEq, {}{derives})]
+{}
+pub struct {}(pub {});
+        "#,
+                if inner.is_copy() { "Copy, " } else { "" },
+                extra(&["Debug", "Clone", "Copy", "PartialEq"]),
+                match backend {
+                    // Postgres describes domain columns of a result by their base type.
+                    Backend::Sqlx => "#[sqlx(transparent)]".to_string(),
+                    Backend::Diesel => type_name("", name),
+                    // Not `#[postgres(transparent)]`: Postgres describes parameters bound to
+                    // domain columns as the domain, which only the domain form of `ToSql`
+                    // accepts. Its `FromSql` still accepts the base type of result columns.
+                    _ => format!("#[postgres(name = {name:?})]"),
+                },
+                AsUpperCamelCase(name),
+                inner
+            ),
+            _ => return Ok(None),
+        };
+        Ok(Some(match backend {
+            Backend::Diesel => definition + &self.build_diesel_impls()?,
+            _ => definition,
+        }))
+    }
+
+    /// diesel's `ToSql` and `FromSql` for a user-defined type, which it has no derive for.
+    fn build_diesel_impls(&self) -> Result<String, Error> {
+        let sql_type = self.diesel_sql_type(true).unwrap_or_default();
+        let (to_sql, from_sql) = match self {
+            Self::Enum { name, variants, .. } => {
+                let (mut to_sql, mut from_sql) = (String::new(), String::new());
+                for (variant, variant_name) in variants.iter().zip(variant_names(name, variants)?) {
+                    let label = byte_string(variant);
+                    let variant = variant_name;
+                    to_sql.push_str(&format!("\n            Self::{variant} => {label},"));
+                    from_sql.push_str(&format!("\n            {label} => Ok(Self::{variant}),"));
+                }
+                (
+                    format!(
+                        r#"let value: &[u8] = match self {{{to_sql}
+        }};
+        std::io::Write::write_all(out, value)?;
+        Ok(diesel::serialize::IsNull::No)"#
+                    ),
+                    format!(
+                        r#"match value.as_bytes() {{{from_sql}
+            other => Err(format!("invalid {name} value {{:?}}", String::from_utf8_lossy(other)).into()),
+        }}"#
+                    ),
+                )
+            }
+            Self::Composite { inner } => {
+                let record = tuple(inner.columns.values().map(|col| {
+                    let sql_type = col.r#type.diesel_sql_type(true).unwrap_or_default();
+                    match col.null {
+                        true => format!("diesel::sql_types::Nullable<{sql_type}>"),
+                        false => sql_type,
+                    }
+                }));
+                let fields = || inner.columns.values().map(|col| AsSnakeCase(&col.name));
+                (
+                    format!(
+                        "diesel::serialize::WriteTuple::<{record}>::write_tuple(&{}, out)",
+                        tuple(fields().map(|field| format!("&self.{field}")))
+                    ),
+                    format!(
+                        "let {} = diesel::deserialize::FromSql::<diesel::sql_types::Record<{record}>, diesel::pg::Pg>::from_sql(value)?;\n        Ok(Self {{ {} }})",
+                        tuple(fields().map(|field| field.to_string())),
+                        fields().map(|field| field.to_string()).collect::<Vec<_>>().join(", ")
+                    ),
+                )
+            }
+            _ => (
+                format!("diesel::serialize::ToSql::<{sql_type}, diesel::pg::Pg>::to_sql(&self.0, out)"),
+                format!("diesel::deserialize::FromSql::<{sql_type}, diesel::pg::Pg>::from_sql(value).map(Self)"),
+            ),
+        };
+        Ok(format!(
+            r#"
+impl diesel::serialize::ToSql<{sql_type}, diesel::pg::Pg> for {0} {{
+    fn to_sql<'b>(&'b self, out: &mut diesel::serialize::Output<'b, '_, diesel::pg::Pg>) -> diesel::serialize::Result {{
+        {to_sql}
+    }}
+}}
+
+impl diesel::deserialize::FromSql<{sql_type}, diesel::pg::Pg> for {0} {{
+    fn from_sql(value: diesel::pg::PgValue<'_>) -> diesel::deserialize::Result<Self> {{
+        {from_sql}
+    }}
+}}
+        "#,
+            self
+        ))
+    }
+
+    /// The diesel SQL type of this type, or `None` if diesel has none. Builtin types are
+    /// qualified with `diesel::sql_types` and user-defined ones with the `sql_types` module
+    /// of [`StructBuilder::build_diesel_schema()`] if `qualified`.
+    pub(crate) fn diesel_sql_type(&self, qualified: bool) -> Option<String> {
+        let builtin = |name: &str| match qualified {
+            true => format!("diesel::sql_types::{name}"),
+            false => name.to_string(),
+        };
+        let user_defined = |name: &str| match qualified {
+            true => format!("sql_types::{}", AsUpperCamelCase(name)),
+            false => AsUpperCamelCase(name).to_string(),
+        };
+        match self {
+            Self::Builtin { inner } | Self::DateTime { inner, .. } => Some(builtin(match *inner {
+                PgType::CHAR => "CChar",
+                PgType::INT2 => "Int2",
+                PgType::INT4 => "Int4",
+                PgType::INT8 => "Int8",
+                PgType::OID => "Oid",
+                PgType::FLOAT4 => "Float4",
+                PgType::FLOAT8 => "Float8",
+                PgType::NUMERIC => "Numeric",
+                PgType::MONEY => "Money",
+                PgType::TEXT => "Text",
+                PgType::BYTEA => "Bytea",
+                PgType::BOOL => "Bool",
+                PgType::INTERVAL => "Interval",
+                PgType::INET => "Inet",
+                PgType::CIDR => "Cidr",
+                PgType::MACADDR => "MacAddr",
+                PgType::INT4_RANGE => "Int4range",
+                PgType::INT8_RANGE => "Int8range",
+                PgType::TS_RANGE => "Tsrange",
+                PgType::TSTZ_RANGE => "Tstzrange",
+                PgType::UUID => "Uuid",
+                PgType::JSON => "Json",
+                PgType::JSONB => "Jsonb",
+                PgType::TIMESTAMP => "Timestamp",
+                PgType::TIMESTAMPTZ => "Timestamptz",
+                PgType::DATE => "Date",
+                PgType::TIME => "Time",
+                _ => return None,
+            })),
+            Self::Array { inner } => Some(format!(
+                "{}<{}>",
+                builtin("Array"),
+                inner.diesel_sql_type(qualified)?
+            )),
+            Self::Enum { name, .. } => Some(user_defined(name)),
+            Self::Composite { inner } => Some(user_defined(&inner.name)),
+            // Values of a domain are sent and received as its base type.
+            Self::Domain { inner, .. } => inner.diesel_sql_type(qualified),
+            Self::Custom { replaces, .. } => replaces.as_ref()?.diesel_sql_type(qualified),
+        }
+    }
+
+    /// Add the declarations of the diesel SQL types of the user-defined types within this
+    /// one, keyed by name.
+    pub(crate) fn collect_diesel_sql_types(&self, sql_types: &mut IndexMap<String, String>) {
+        let (name, schema) = match self {
+            Self::Enum { name, schema, .. } => (name, schema),
+            Self::Composite { inner } => {
+                for col in inner.columns.values() {
+                    col.r#type.collect_diesel_sql_types(sql_types);
+                }
+                (&inner.name, &inner.schema)
+            }
+            Self::Array { inner } | Self::Domain { inner, .. } => {
+                return inner.collect_diesel_sql_types(sql_types)
+            }
+            Self::Custom {
+                replaces: Some(inner),
+                ..
+            } => return inner.collect_diesel_sql_types(sql_types),
+            _ => return,
+        };
+        sql_types
+            .entry(AsUpperCamelCase(name).to_string())
+            .or_insert_with(|| {
+                format!(
+                    r#"    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = {name:?}, schema = {schema:?}))]
+    pub struct {};
+"#,
+                    AsUpperCamelCase(name)
+                )
+            });
+    }
+
+    /// The first type, this one or a nested one, that `backend` can't encode or decode.
+    pub(crate) fn unsupported_by(&self, backend: Backend) -> Option<&Type> {
+        match (self, backend) {
+            (Self::Composite { inner }, _) => inner
+                .columns
+                .values()
+                .find_map(|col| col.r#type.unsupported_by(backend)),
+            (Self::Array { inner } | Self::Domain { inner, .. }, _) => {
+                inner.unsupported_by(backend)
+            }
+            (
+                Self::DateTime {
+                    time_crate: TimeCrate::Jiff,
+                    ..
+                },
+                Backend::Sqlx | Backend::Diesel,
+            ) => Some(self),
+            // Without the type it replaces, there is no SQL type for the column.
+            (Self::Custom { replaces: None, .. }, Backend::Diesel) => Some(self),
+            (Self::Builtin { inner }, Backend::Sqlx)
+                if [
+                    PgType::OID,
+                    PgType::MONEY,
+                    PgType::INTERVAL,
+                    PgType::CIDR,
+                    PgType::MACADDR,
+                    PgType::INT4_RANGE,
+                    PgType::INT8_RANGE,
+                    PgType::TS_RANGE,
+                    PgType::TSTZ_RANGE,
+                ]
+                .contains(inner) =>
+            {
+                Some(self)
+            }
+            (Self::Builtin { inner }, Backend::Diesel)
+                if [
+                    PgType::CHAR,
+                    PgType::MONEY,
+                    PgType::INTERVAL,
+                    PgType::INET,
+                    PgType::CIDR,
+                    PgType::MACADDR,
+                    PgType::INT4_RANGE,
+                    PgType::INT8_RANGE,
+                    PgType::TS_RANGE,
+                    PgType::TSTZ_RANGE,
+                ]
+                .contains(inner) =>
+            {
+                Some(self)
+            }
+            _ => None,
+        }
+    }
+
+    /// Collect the definitions of this type and the user-defined types it depends on,
+    /// dependencies first, keyed by schema and Postgres name. Types of different schemas
+    /// that would get the same Rust name are an [`Error::AmbiguousType`].
+    pub(crate) fn collect_definitions(
+        &self,
+        definitions: &mut IndexMap<(String, String), String>,
+        backend: Backend,
+        derives: &[&str],
+    ) -> Result<(), Error> {
+        self.check()?;
+        match self {
+            Self::Composite { inner } => {
+                for col in inner.columns.values() {
+                    col.r#type
+                        .collect_definitions(definitions, backend, derives)?;
+                }
+            }
+            Self::Array { inner } => {
+                return inner.collect_definitions(definitions, backend, derives)
             }
-            | Self::Composite { inner: _ } => false,
-            _ => todo!(),
+            Self::Domain { inner, .. } => {
+                inner.collect_definitions(definitions, backend, derives)?
+            }
+            _ => {}
+        }
+        let key = match self {
+            Self::Composite { inner } => (inner.schema.to_string(), inner.name.to_string()),
+            Self::Enum { name, schema, .. }
+            | Self::Domain {
+                name,
+                schema,
+                mode: DomainMode::Newtype,
+                ..
+            } => (schema.to_string(), name.to_string()),
+            _ => return Ok(()),
+        };
+        if definitions.contains_key(&key) {
+            return Ok(());
+        }
+        let rust_type = AsUpperCamelCase(&key.1).to_string();
+        if let Some((schema, name)) = definitions
+            .keys()
+            .find(|(_, name)| AsUpperCamelCase(name).to_string() == rust_type)
+        {
+            return Err(Error::AmbiguousType {
+                first: format!("{schema}.{name}").into(),
+                second: format!("{}.{}", key.0, key.1).into(),
+                rust_type: rust_type.into(),
+            });
+        }
+        if let Some(definition) = self.build_definition(backend, derives)? {
+            definitions.insert(key, definition);
+        }
+        Ok(())
+    }
+}
+
+/// A tuple of `items`, with the trailing comma a single one needs.
+fn tuple(items: impl Iterator<Item = String>) -> String {
+    let items = items.collect::<Vec<_>>();
+    match items.len() {
+        1 => format!("({},)", items[0]),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+/// The Rust variant names of the labels of the enum `name`, prefixing those that aren't
+/// identifiers on their own, like `1st`, with `_`.
+fn variant_names(name: &str, labels: &[Cow<'static, str>]) -> Result<Vec<String>, Error> {
+    let mut names = Vec::<String>::with_capacity(labels.len());
+    for label in labels {
+        let mut variant = AsUpperCamelCase(label).to_string();
+        if variant.starts_with(|c: char| c.is_ascii_digit()) || variant == "Self" {
+            variant.insert(0, '_');
         }
+        if variant.is_empty() || names.contains(&variant) {
+            return Err(Error::InvalidVariant {
+                pg_type: name.to_string().into(),
+                label: label.clone(),
+            });
+        }
+        names.push(variant);
     }
+    Ok(names)
+}
+
+/// A byte string literal of `text`.
+fn byte_string(text: &str) -> String {
+    let escaped = text
+        .bytes()
+        .flat_map(std::ascii::escape_default)
+        .map(char::from)
+        .collect::<String>();
+    format!("b\"{escaped}\"")
 }
 
 pub struct TypeAsRef<'a> {
@@ -107,80 +1000,58 @@ pub struct TypeAsRef<'a> {
     pub val: &'a Type,
 }
 
-impl std::fmt::Display for TypeAsRef<'_> {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl TypeAsRef<'_> {
+    /// The Rust type parameters of this type are passed as, borrowed for `lifetime` unless
+    /// it is `Copy`; [`Error::UnknownType`] if there is none.
+    pub fn rust_type(&self) -> Result<String, Error> {
         let Self { val, lifetime } = self;
-        match val {
-            Type::Builtin {
-                inner: PgType::INT8,
-            } => write!(fmt, "i64"),
-            Type::Builtin {
-                inner: PgType::INT4,
-            } => write!(fmt, "i32"),
+        let borrow = match lifetime {
+            Some(lifetime) => format!("&'{lifetime} "),
+            None => "&".to_string(),
+        };
+        Ok(match val {
             Type::Builtin {
                 inner: PgType::TEXT,
-            } => write!(
-                fmt,
-                "&{}{}{}str",
-                if lifetime.is_some() { "'" } else { "" },
-                if let Some(l) = lifetime.as_ref() {
-                    *l
-                } else {
-                    ""
-                },
-                if lifetime.is_some() { " " } else { "" }
-            ),
-            Type::Builtin {
-                inner: PgType::TEXT_ARRAY,
-            } => write!(
-                fmt,
-                "Vec<&{}{}{}str>",
-                if lifetime.is_some() { "'" } else { "" },
-                if let Some(l) = lifetime.as_ref() {
-                    *l
-                } else {
-                    ""
-                },
-                if lifetime.is_some() { " " } else { "" }
-            ),
+            } => format!("{borrow}str"),
             Type::Builtin {
                 inner: PgType::BYTEA,
-            } => write!(fmt, "Vec<u8>"),
-            Type::Builtin {
-                inner: PgType::BYTEA_ARRAY,
-            } => write!(
-                fmt,
-                "Vec<&{}{}{}[u8]>",
-                if lifetime.is_some() { "'" } else { "" },
-                if let Some(l) = lifetime.as_ref() {
-                    *l
-                } else {
-                    ""
-                },
-                if lifetime.is_some() { " " } else { "" }
-            ),
-            Type::Builtin {
-                inner: PgType::BOOL,
-            } => write!(fmt, "bool"),
-            Type::Builtin {
-                inner: PgType::TIMESTAMP,
-            } => write!(fmt, "chrono::naive::NaiveDateTime",),
+            } => format!("{borrow}[u8]"),
             Type::Builtin {
-                inner: PgType::TIMESTAMPTZ,
-            } => write!(fmt, "chrono::DateTime<chrono::Utc>",),
-            Type::Composite { inner } => write!(
-                fmt,
-                "&{}{}{}{}",
-                if lifetime.is_some() { "'" } else { "" },
-                if let Some(l) = lifetime.as_ref() {
-                    *l
-                } else {
-                    ""
-                },
-                if lifetime.is_some() { " " } else { "" },
-                AsUpperCamelCase(&inner.name)
-            ),
-            _ => todo!(),
+                inner: PgType::JSON,
+            }
+            | Type::Builtin {
+                inner: PgType::JSONB,
+            } => format!("{borrow}serde_json::Value"),
+            Type::Composite { inner } => format!("{borrow}{}", AsUpperCamelCase(&inner.name)),
+            Type::Array { inner } => format!("{borrow}[{}]", inner.rust_type()?),
+            Type::Domain {
+                name,
+                inner,
+                mode: DomainMode::Newtype,
+                ..
+            } if !inner.is_copy() => format!("{borrow}{}", AsUpperCamelCase(name)),
+            Type::Domain {
+                inner,
+                mode: DomainMode::BaseType,
+                ..
+            } => TypeAsRef {
+                lifetime: *lifetime,
+                val: inner,
+            }
+            .rust_type()?,
+            Type::Custom { path, .. } => format!("{borrow}{path}"),
+            // Everything else is `Copy` and passed by value.
+            _ => val.rust_type()?,
+        })
+    }
+}
+
+/// Writes the Rust type of parameters of this type, with the same fallback as [`Type`].
+impl std::fmt::Display for TypeAsRef<'_> {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.rust_type() {
+            Ok(rust_type) => fmt.write_str(&rust_type),
+            Err(err) => write!(fmt, "<{err}>"),
         }
     }
 }
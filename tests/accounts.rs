@@ -23,11 +23,14 @@ pub struct AccountsNew<'a> {
 }
 
 impl Accounts {
+    /// The columns of the table in the order of the fields, as a list to select.
+    pub const COLUMNS: &'static str = "user_id, username, password, email, created_on, last_login";
+
     pub fn insert_slice(
         client: &mut postgres::Client,
         slice: &[AccountsNew<'_>],
     ) -> Result<(), postgres::Error> {
-        let statement = client.prepare("INSERT INTO accounts(username, password, email, created_on, last_login) VALUES($1, $2, $3, $4, $5);")?;
+        let statement = client.prepare("INSERT INTO public.accounts(username, password, email, created_on, last_login) VALUES($1, $2, $3, $4, $5);")?;
         for entry in slice {
             client.execute(
                 &statement,
@@ -42,6 +45,145 @@ impl Accounts {
         }
         Ok(())
     }
+
+    /// Like `insert_slice`, returning the inserted rows with their generated and default
+    /// values.
+    pub fn insert_slice_returning(
+        client: &mut postgres::Client,
+        slice: &[AccountsNew<'_>],
+    ) -> Result<Vec<Self>, postgres::Error> {
+        let mut rows = Vec::with_capacity(slice.len());
+        let statement = client.prepare("INSERT INTO public.accounts(username, password, email, created_on, last_login) VALUES($1, $2, $3, $4, $5) RETURNING user_id, username, password, email, created_on, last_login;")?;
+        for entry in slice {
+            rows.push(Self::try_from(&client.query_one(
+                &statement,
+                &[
+                    &entry.username,
+                    &entry.password,
+                    &entry.email,
+                    &entry.created_on,
+                    &entry.last_login,
+                ],
+            )?)?);
+        }
+        Ok(rows)
+    }
+
+    /// Like `insert_slice_returning`, for a single entry.
+    pub fn insert_returning(
+        client: &mut postgres::Client,
+        entry: &AccountsNew<'_>,
+    ) -> Result<Self, postgres::Error> {
+        let mut rows = Self::insert_slice_returning(client, std::slice::from_ref(entry))?;
+        Ok(rows.remove(0))
+    }
+
+    /// Insert the entries, or update the existing row with the same `email` instead.
+    pub fn upsert_slice(
+        client: &mut postgres::Client,
+        slice: &[AccountsNew<'_>],
+    ) -> Result<(), postgres::Error> {
+        let statement = client.prepare("INSERT INTO public.accounts(username, password, email, created_on, last_login) VALUES($1, $2, $3, $4, $5) ON CONFLICT (email) DO UPDATE SET username = EXCLUDED.username, password = EXCLUDED.password, created_on = EXCLUDED.created_on, last_login = EXCLUDED.last_login;")?;
+        for entry in slice {
+            client.execute(
+                &statement,
+                &[
+                    &entry.username,
+                    &entry.password,
+                    &entry.email,
+                    &entry.created_on,
+                    &entry.last_login,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Like `upsert_slice`, for a single entry.
+    pub fn upsert(
+        client: &mut postgres::Client,
+        entry: &AccountsNew<'_>,
+    ) -> Result<(), postgres::Error> {
+        Self::upsert_slice(client, std::slice::from_ref(entry))
+    }
+
+    /// The row with the given primary key, if there is one.
+    pub fn get(
+        client: &mut postgres::Client,
+        user_id: i32,
+    ) -> Result<Option<Self>, postgres::Error> {
+        let row = client.query_opt("SELECT user_id, username, password, email, created_on, last_login FROM public.accounts WHERE user_id = $1;", &[&user_id])?;
+        row.as_ref().map(Self::try_from).transpose()
+    }
+
+    /// The rows with the given primary keys, skipping those without one.
+    pub fn get_many(
+        client: &mut postgres::Client,
+        keys: &[i32],
+    ) -> Result<Vec<Self>, postgres::Error> {
+        client
+            .query("SELECT user_id, username, password, email, created_on, last_login FROM public.accounts WHERE user_id = ANY($1);", &[&keys])?
+            .iter()
+            .map(Self::try_from)
+            .collect()
+    }
+
+    /// Write the fields of `self` to the row with its primary key, returning whether
+    /// there is one.
+    pub fn update(&self, client: &mut postgres::Client) -> Result<bool, postgres::Error> {
+        Ok(client.execute("UPDATE public.accounts SET username = $2, password = $3, email = $4, created_on = $5, last_login = $6 WHERE user_id = $1;", &[&self.user_id, &self.username, &self.password, &self.email, &self.created_on, &self.last_login])? > 0)
+    }
+
+    /// Delete the row with the given primary key, returning whether there was one.
+    pub fn delete(client: &mut postgres::Client, user_id: i32) -> Result<bool, postgres::Error> {
+        Ok(client.execute(
+            "DELETE FROM public.accounts WHERE user_id = $1;",
+            &[&user_id],
+        )? > 0)
+    }
+
+    /// Whether there is a row with the given primary key.
+    pub fn exists(client: &mut postgres::Client, user_id: i32) -> Result<bool, postgres::Error> {
+        client
+            .query_one(
+                "SELECT EXISTS(SELECT 1 FROM public.accounts WHERE user_id = $1);",
+                &[&user_id],
+            )?
+            .try_get(0)
+    }
+
+    /// The row with the given `email`, if there is one.
+    pub fn find_by_email(
+        client: &mut postgres::Client,
+        email: &str,
+    ) -> Result<Option<Self>, postgres::Error> {
+        let row = client.query_opt("SELECT user_id, username, password, email, created_on, last_login FROM public.accounts WHERE email = $1;", &[&email])?;
+        row.as_ref().map(Self::try_from).transpose()
+    }
+
+    /// The row with the given `username`, if there is one.
+    pub fn find_by_username(
+        client: &mut postgres::Client,
+        username: &str,
+    ) -> Result<Option<Self>, postgres::Error> {
+        let row = client.query_opt("SELECT user_id, username, password, email, created_on, last_login FROM public.accounts WHERE username = $1;", &[&username])?;
+        row.as_ref().map(Self::try_from).transpose()
+    }
+}
+
+impl TryFrom<&postgres::Row> for Accounts {
+    type Error = postgres::Error;
+
+    fn try_from(row: &postgres::Row) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user_id: row.try_get("user_id")?,
+            username: row.try_get("username")?,
+            password: row.try_get("password")?,
+            email: row.try_get("email")?,
+            created_on: row.try_get("created_on")?,
+            last_login: row.try_get("last_login")?,
+        })
+    }
 }
 
 #[test]
@@ -104,14 +246,101 @@ fn test_accounts() {
 
     Accounts::insert_slice(client, &[new_val_1, new_val_2, new_val_3, new_val_4]).unwrap();
 
-    for row in client
-        .query("SELECT user_id, username FROM accounts;", &[])
+    let accounts = client
+        .query(
+            &format!(
+                "SELECT {} FROM accounts ORDER BY username;",
+                Accounts::COLUMNS
+            ),
+            &[],
+        )
         .unwrap()
-    {
-        let id: i32 = row.get(0);
-        let name: &str = row.get(1);
-        println!("found person: {} {}", id, name);
+        .iter()
+        .map(Accounts::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(accounts.len(), 4);
+    for account in &accounts {
+        assert_eq!(account.password, password);
+        assert_eq!(account.last_login, None);
+        println!("found person: {} {}", account.user_id, account.username);
     }
+    assert_eq!(accounts[0].email, "foo1@example.com");
+
+    let mut account = Accounts::get(client, accounts[0].user_id).unwrap().unwrap();
+    assert_eq!(account.username, "user1");
+    account.last_login = Some(account.created_on);
+    assert!(account.update(client).unwrap());
+    let account = Accounts::get(client, account.user_id).unwrap().unwrap();
+    assert_eq!(account.last_login, Some(account.created_on));
+
+    let keys = [accounts[1].user_id, accounts[2].user_id, -1];
+    let mut found = Accounts::get_many(client, &keys).unwrap();
+    found.sort_by_key(|account| account.user_id);
+    assert_eq!(
+        found
+            .iter()
+            .map(|a| a.username.as_str())
+            .collect::<Vec<_>>(),
+        ["user2", "user3"]
+    );
+
+    let found = Accounts::find_by_email(client, "foo3@example.com")
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.username, "user3");
+    let found = Accounts::find_by_username(client, "user4")
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.email, "foo4@example.com");
+    assert!(Accounts::find_by_username(client, "nobody")
+        .unwrap()
+        .is_none());
+
+    let new_password = "changed".to_string();
+    Accounts::upsert(
+        client,
+        &AccountsNew {
+            username: "user3",
+            password: &new_password,
+            email: "foo3@example.com",
+            created_on,
+            last_login,
+        },
+    )
+    .unwrap();
+    let found = Accounts::find_by_email(client, "foo3@example.com")
+        .unwrap()
+        .unwrap();
+    assert_eq!(found.user_id, accounts[2].user_id);
+    assert_eq!(found.password, "changed");
+
+    assert!(Accounts::exists(client, account.user_id).unwrap());
+    assert!(Accounts::delete(client, account.user_id).unwrap());
+    assert!(!Accounts::delete(client, account.user_id).unwrap());
+    assert!(!Accounts::exists(client, account.user_id).unwrap());
+    assert!(Accounts::get(client, account.user_id).unwrap().is_none());
+
+    let inserted = Accounts::insert_returning(
+        client,
+        &AccountsNew {
+            username: "user5",
+            password: &password,
+            email: "foo5@example.com",
+            created_on,
+            last_login,
+        },
+    )
+    .unwrap();
+    assert!(accounts.iter().all(|a| a.user_id < inserted.user_id));
+    assert_eq!(
+        Accounts::get(client, inserted.user_id)
+            .unwrap()
+            .map(|a| a.username),
+        Some("user5".to_string())
+    );
+    let inserted = Accounts::insert_slice_returning(client, &[]).unwrap();
+    assert!(inserted.is_empty());
 
     // clean up what we did
     client.batch_execute(r#"DELETE FROM accounts;"#).unwrap();
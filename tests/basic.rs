@@ -1,13 +1,73 @@
-use instant_models::StructBuilder;
+use indexmap::IndexMap;
+use instant_models::{
+    Backend, Column, Constraint, DatabaseBuilder, DomainMode, Error, ForeignKey, Identity,
+    OnConflict, StructAttributes, StructBuilder, TimeCrate, Type, TypeOverrides,
+};
 use postgres::{Config, NoTls}; // Client
 use std::fs::File;
 use std::fs::OpenOptions;
 use std::io::prelude::*;
+use std::path::Path;
 use std::process::Command;
 
-/// Create a library crate and make sure the generated code type checks.
-fn create_cargo_project(builder: StructBuilder) -> Result<(), anyhow::Error> {
+/// Create a library crate in `dir` and make sure the generated code type checks.
+fn create_cargo_project(dir: &Path, builder: StructBuilder) -> Result<(), anyhow::Error> {
+    init_cargo_project(dir)?;
+    let mut file = File::create(dir.join("src/lib.rs"))?;
+
+    let header = r#"#![allow(dead_code)]
+
+"#;
+    file.write_all(header.as_bytes())?;
+    file.write_all(builder.build_user_types()?.as_bytes())?;
+    file.write_all(builder.build_type()?.as_bytes())?;
+    file.write_all(builder.build_new_type()?.as_bytes())?;
+    file.write_all(builder.build_type_methods()?.as_bytes())?;
+    drop(file);
+    check_cargo_project(dir)
+}
+
+/// Generate the tables of `schema` for each backend, with the `DatabaseBuilder` set up by
+/// `configure`, and make sure the modules type check. Returns the modules of each backend.
+fn check_database(
+    client: &mut postgres::Client,
+    schema: &str,
+    configure: impl Fn(&mut DatabaseBuilder),
+) -> Vec<(Backend, IndexMap<String, String>)> {
+    let mut generated = vec![];
+    for backend in [
+        Backend::Postgres,
+        Backend::TokioPostgres,
+        Backend::Sqlx,
+        Backend::Diesel,
+    ] {
+        let mut database = DatabaseBuilder::new();
+        database
+            .set_schemas(vec![schema.to_string().into()])
+            .set_backend(backend);
+        configure(&mut database);
+        database.introspect(client).unwrap();
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ret = init_cargo_project(tmpdir.path()).and_then(|()| {
+            database.write_modules(&tmpdir.path().join("src/db"))?;
+            std::fs::write(
+                tmpdir.path().join("src/lib.rs"),
+                "#![allow(dead_code)]\npub mod db;\n",
+            )?;
+            check_cargo_project(tmpdir.path())
+        });
+        tmpdir.close().unwrap();
+        ret.unwrap();
+        generated.push((backend, database.build_modules().unwrap()));
+    }
+    generated
+}
+
+/// Create a library crate in `dir` with the dependencies of the generated code.
+fn init_cargo_project(dir: &Path) -> Result<(), anyhow::Error> {
     let cargo = Command::new("cargo")
+        .current_dir(dir)
         .arg("init")
         .arg("--lib")
         .arg("--name")
@@ -19,53 +79,89 @@ fn create_cargo_project(builder: StructBuilder) -> Result<(), anyhow::Error> {
     if !cargo.status.success() {
         return Err(anyhow::anyhow!("cargo init returned error"));
     }
-    let mut file = File::create("./src/lib.rs")?;
-
-    let header = r#"#![allow(dead_code)]
-
-"#;
-    file.write_all(header.as_bytes())?;
-    file.write_all(builder.build_type().as_bytes())?;
-    file.write_all(builder.build_new_type().as_bytes())?;
-    file.write_all(builder.build_type_methods().as_bytes())?;
-    drop(file);
     let mut manifest_file = OpenOptions::new()
-        .write(true)
         .append(true)
-        .open("./Cargo.toml")?;
+        .open(dir.join("Cargo.toml"))?;
 
     manifest_file.write_all(
         r#"
 chrono = "0.4"
-postgres = { version = "0.19.3", features = ["with-chrono-0_4", ] }
+cidr = "0.3"
+diesel = { version = "2.2", default-features = false, features = ["postgres", "chrono", "time", "uuid", "serde_json"] }
+eui48 = "1"
+jiff = "0.2"
+pg_interval = "0.6"
+postgres = { version = "0.19.3", features = ["with-chrono-0_4", "with-cidr-0_3", "with-eui48-1", "with-jiff-0_2", "with-serde_json-1", "with-time-0_3", "with-uuid-1"] }
+postgres-types = { version = "0.2", features = ["derive"] }
+postgres_money = { version = "0.4", features = ["sql"] }
+postgres_range = { version = "0.11", features = ["with-chrono-0_4"] }
+rust_decimal = { version = "1", features = ["db-postgres", "db-diesel2-postgres"] }
+serde = { version = "1", features = ["derive"] }
+serde_json = "1"
+sqlx = { version = "0.8", default-features = false, features = ["postgres", "runtime-tokio", "derive", "chrono", "time", "uuid", "json", "rust_decimal", "ipnetwork"] }
+time = "0.3"
+tokio-postgres = "0.7"
+uuid = "1"
 "#
         .as_bytes(),
     )?;
     drop(manifest_file);
+    Ok(())
+}
+
+/// Make sure the crate in `dir` type checks.
+fn check_cargo_project(dir: &Path) -> Result<(), anyhow::Error> {
+    // Share one target directory between the generated crates so their
+    // dependencies only get built once.
     let check = Command::new("cargo")
+        .current_dir(dir)
+        .env(
+            "CARGO_TARGET_DIR",
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("target/basictest"),
+        )
         .arg("check")
         //.stdout(Stdio::inherit())
         //.stderr(Stdio::inherit())
         .output()
         .expect("failed to execute process");
     if !check.status.success() {
-        return Err(anyhow::anyhow!("cargo check returned error"));
+        return Err(anyhow::anyhow!(
+            "cargo check returned error:\n{}",
+            String::from_utf8_lossy(&check.stderr)
+        ));
     }
 
     Ok(())
 }
 
-#[test]
-#[ignore]
-fn test_basic() {
-    let client = &mut Config::new()
+fn connect() -> postgres::Client {
+    Config::new()
         .user("postgres")
         .password("postgres")
         .host("127.0.0.1")
         .port(5432)
         .dbname("postgres")
         .connect(NoTls)
+        .unwrap()
+}
+
+/// Connect a `tokio_postgres::Client`, driving its connection on `runtime`.
+#[cfg(feature = "tokio-postgres")]
+fn connect_async(runtime: &tokio::runtime::Runtime) -> tokio_postgres::Client {
+    let (client, connection) = runtime
+        .block_on(tokio_postgres::connect(
+            "host=127.0.0.1 port=5432 user=postgres password=postgres dbname=postgres",
+            tokio_postgres::NoTls,
+        ))
         .unwrap();
+    runtime.spawn(connection);
+    client
+}
+
+#[test]
+#[ignore]
+fn test_basic() {
+    let client = &mut connect();
 
     const TABLE_NAME: &str = "accounts";
 
@@ -84,7 +180,7 @@ fn test_basic() {
 
     let struct_bldr = StructBuilder::new_from_conn(client, TABLE_NAME).unwrap();
     assert_eq!(struct_bldr.columns.len(), 6);
-    let result = struct_bldr.build_type();
+    let result = struct_bldr.build_type().unwrap();
     println!("final:\n{}", &result);
     assert_eq!(
         result.replace([' ', '\r', '\n'], ""),
@@ -99,11 +195,2142 @@ fn test_basic() {
         .replace([' ', '\r', '\n'], "")
     );
 
-    let cwd = std::env::current_dir().unwrap();
     let tmpdir = tempfile::tempdir().unwrap();
-    std::env::set_current_dir(tmpdir.path()).unwrap();
-    let ret = create_cargo_project(struct_bldr);
-    std::env::set_current_dir(cwd).unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_numeric() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS measurements;
+CREATE TABLE measurements (
+        measurement_id bigserial PRIMARY KEY,
+        sensor smallint NOT NULL,
+        kind "char" NOT NULL,
+        reading real NOT NULL,
+        precise_reading double precision,
+        calibrated numeric(10, 2) NOT NULL,
+        cost money,
+        source oid
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "measurements").unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Measurements {
+    pub measurement_id: i64,
+    pub sensor: i16,
+    pub kind: i8,
+    pub reading: f32,
+    pub precise_reading: Option<f64>,
+    pub calibrated: rust_decimal::Decimal,
+    pub cost: Option<postgres_money::Money>,
+    pub source: Option<u32>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_uuid_json() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS events;
+CREATE TABLE events (
+        event_id uuid PRIMARY KEY,
+        correlation_id uuid,
+        payload jsonb NOT NULL,
+        raw_payload json
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "events").unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Events {
+    pub event_id: uuid::Uuid,
+    pub correlation_id: Option<uuid::Uuid>,
+    pub payload: serde_json::Value,
+    pub raw_payload: Option<serde_json::Value>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct EventsNew<'a> {
+    pub event_id: uuid::Uuid,
+    pub correlation_id: Option<uuid::Uuid>,
+    pub payload: &'a serde_json::Value,
+    pub raw_payload: Option<&'a serde_json::Value>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_enum() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS tickets;
+DROP TYPE IF EXISTS ticket_status;
+CREATE TYPE ticket_status AS ENUM ('open', 'in_progress', 'closed');
+CREATE TABLE tickets (
+        ticket_id serial PRIMARY KEY,
+        title TEXT NOT NULL,
+        status ticket_status NOT NULL,
+        previous_status ticket_status
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "tickets").unwrap();
+    assert_eq!(
+        struct_bldr.build_user_types().unwrap().replace([' ', '\r', '\n'], ""),
+        r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "ticket_status")]
+pub enum TicketStatus {
+    #[postgres(name = "open")]
+    Open,
+    #[postgres(name = "in_progress")]
+    InProgress,
+    #[postgres(name = "closed")]
+    Closed,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Tickets {
+    pub ticket_id: i32,
+    pub title: String,
+    pub status: TicketStatus,
+    pub previous_status: Option<TicketStatus>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+
+    // Labels that aren't identifiers, or that need escaping in the attributes.
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS enum_test CASCADE;
+CREATE SCHEMA enum_test;
+CREATE TYPE enum_test.mood AS ENUM ('1st', 'ok', 'not-ok', 'say "hi"', 'back\slash', 'self');
+CREATE TABLE enum_test.moods (
+        mood_id serial PRIMARY KEY,
+        mood enum_test.mood NOT NULL
+);"#,
+        )
+        .unwrap();
+    let mut struct_bldr = StructBuilder::new("moods".into());
+    struct_bldr
+        .set_schema("enum_test".into())
+        .introspect(client)
+        .unwrap();
+    let user_types = struct_bldr.build_user_types().unwrap();
+    assert!(user_types.contains("    #[postgres(name = \"1st\")]\n    _1st,\n"));
+    assert!(user_types.contains("    #[postgres(name = \"say \\\"hi\\\"\")]\n    SayHi,\n"));
+    assert!(user_types.contains("    #[postgres(name = \"back\\\\slash\")]\n    BackSlash,\n"));
+    assert!(user_types.contains("    _Self,\n"));
+    check_database(client, "enum_test", |_| {});
+
+    client
+        .batch_execute("ALTER TYPE enum_test.mood ADD VALUE 'OK';")
+        .unwrap();
+    let mut struct_bldr = StructBuilder::new("moods".into());
+    struct_bldr
+        .set_schema("enum_test".into())
+        .introspect(client)
+        .unwrap();
+    match struct_bldr.build_user_types() {
+        Err(Error::InvalidVariant { pg_type, label }) => {
+            assert_eq!(pg_type, "mood");
+            assert_eq!(label, "OK");
+        }
+        other => panic!("expected an invalid variant error, got {other:?}"),
+    }
+}
+
+#[test]
+#[ignore]
+fn test_composite() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS shipments;
+DROP TYPE IF EXISTS shipping_address;
+DROP TYPE IF EXISTS address_kind;
+CREATE TYPE address_kind AS ENUM ('home', 'business');
+CREATE TYPE shipping_address AS (
+        street TEXT,
+        "postalCode" TEXT,
+        kind address_kind,
+        coordinates double precision
+);
+CREATE TABLE shipments (
+        shipment_id serial PRIMARY KEY,
+        destination shipping_address NOT NULL,
+        origin shipping_address
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "shipments").unwrap();
+    assert_eq!(
+        struct_bldr.build_user_types().unwrap().replace([' ', '\r', '\n'], ""),
+        r#"#[derive(Debug, Clone, Copy, PartialEq, Eq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "address_kind")]
+pub enum AddressKind {
+    #[postgres(name = "home")]
+    Home,
+    #[postgres(name = "business")]
+    Business,
+}
+#[derive(Debug, Clone, PartialEq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "shipping_address")]
+pub struct ShippingAddress {
+    pub street: Option<String>,
+    #[postgres(name = "postalCode")]
+    pub postal_code: Option<String>,
+    pub kind: Option<AddressKind>,
+    pub coordinates: Option<f64>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct ShipmentsNew<'a> {
+    pub destination: &'a ShippingAddress,
+    pub origin: Option<&'a ShippingAddress>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_array() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS surveys;
+DROP TYPE IF EXISTS survey_answer;
+DROP TYPE IF EXISTS survey_mood;
+CREATE TYPE survey_mood AS ENUM ('happy', 'sad');
+CREATE TYPE survey_answer AS (
+        question TEXT,
+        scores integer[]
+);
+CREATE TABLE surveys (
+        survey_id serial PRIMARY KEY,
+        scores integer[] NOT NULL,
+        tags TEXT[],
+        attachments bytea[] NOT NULL,
+        moods survey_mood[] NOT NULL,
+        answers survey_answer[]
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "surveys").unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Surveys {
+    pub survey_id: i32,
+    pub scores: Vec<i32>,
+    pub tags: Option<Vec<String>>,
+    pub attachments: Vec<Vec<u8>>,
+    pub moods: Vec<SurveyMood>,
+    pub answers: Option<Vec<SurveyAnswer>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct SurveysNew<'a> {
+    pub scores: &'a [i32],
+    pub tags: Option<&'a [String]>,
+    pub attachments: &'a [Vec<u8>],
+    pub moods: &'a [SurveyMood],
+    pub answers: Option<&'a [SurveyAnswer]>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert!(struct_bldr
+        .build_user_types()
+        .unwrap()
+        .contains("pub scores: Option<Vec<i32>>,"));
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_date_time_network_range() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS schedules;
+CREATE TABLE schedules (
+        schedule_id serial PRIMARY KEY,
+        day date NOT NULL,
+        starts_at time NOT NULL,
+        duration interval NOT NULL,
+        client_ip inet,
+        allowed_network cidr,
+        device macaddr,
+        seats int4range,
+        budget int8range,
+        local_window tsrange,
+        window_utc tstzrange NOT NULL
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "schedules").unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Schedules {
+    pub schedule_id: i32,
+    pub day: chrono::NaiveDate,
+    pub starts_at: chrono::NaiveTime,
+    pub duration: pg_interval::Interval,
+    pub client_ip: Option<std::net::IpAddr>,
+    pub allowed_network: Option<cidr::IpCidr>,
+    pub device: Option<eui48::MacAddress>,
+    pub seats: Option<postgres_range::Range<i32>>,
+    pub budget: Option<postgres_range::Range<i64>>,
+    pub local_window: Option<postgres_range::Range<chrono::naive::NaiveDateTime>>,
+    pub window_utc: postgres_range::Range<chrono::DateTime<chrono::Utc>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_time_crate() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS appointments;
+CREATE TABLE appointments (
+        appointment_id serial PRIMARY KEY,
+        booked_at timestamp with time zone NOT NULL,
+        starts_at timestamp NOT NULL,
+        day date NOT NULL,
+        reminder time,
+        history timestamp with time zone[]
+);"#,
+        )
+        .unwrap();
+
+    let mut struct_bldr = StructBuilder::new_from_conn(client, "appointments").unwrap();
+    struct_bldr.set_time_crate(TimeCrate::Time);
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Appointments {
+    pub appointment_id: i32,
+    pub booked_at: time::OffsetDateTime,
+    pub starts_at: time::PrimitiveDateTime,
+    pub day: time::Date,
+    pub reminder: Option<time::Time>,
+    pub history: Option<Vec<time::OffsetDateTime>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+
+    let mut struct_bldr = StructBuilder::new_from_conn(client, "appointments").unwrap();
+    struct_bldr.set_time_crate(TimeCrate::Jiff);
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Appointments {
+    pub appointment_id: i32,
+    pub booked_at: jiff::Timestamp,
+    pub starts_at: jiff::civil::DateTime,
+    pub day: jiff::civil::Date,
+    pub reminder: Option<jiff::civil::Time>,
+    pub history: Option<Vec<jiff::Timestamp>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+fn test_type_overrides() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"CREATE EXTENSION IF NOT EXISTS citext;
+DROP TABLE IF EXISTS customers;
+DROP SCHEMA IF EXISTS invoicing CASCADE;
+DROP DOMAIN IF EXISTS money_cents;
+CREATE DOMAIN money_cents AS bigint CHECK (VALUE >= 0);
+CREATE TABLE customers (
+        customer_id serial PRIMARY KEY,
+        email TEXT NOT NULL,
+        nickname citext,
+        aliases citext[],
+        balance money_cents NOT NULL,
+        created_on TIMESTAMP NOT NULL
+);
+CREATE SCHEMA invoicing;
+CREATE TABLE invoicing.customers (
+        customer_id serial PRIMARY KEY,
+        email TEXT NOT NULL,
+        invoice_email TEXT
+);"#,
+        )
+        .unwrap();
+
+    let overrides: TypeOverrides = r#"
+# column overrides
+customers.email = crate::Email
+customers.invoice_email = crate::Email
+invoicing.customers.email = crate::BillingEmail
+
+citext = String
+money_cents = Cents
+"#
+    .parse()
+    .unwrap();
+    let mut struct_bldr = StructBuilder::new("customers".into());
+    struct_bldr
+        .set_type_overrides(overrides.clone())
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Customers {
+    pub customer_id: i32,
+    pub email: crate::Email,
+    pub nickname: Option<String>,
+    pub aliases: Option<Vec<String>>,
+    pub balance: Cents,
+    pub created_on: chrono::naive::NaiveDateTime,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct CustomersNew<'a> {
+    pub email: &'a crate::Email,
+    pub nickname: Option<&'a String>,
+    pub aliases: Option<&'a [String]>,
+    pub balance: &'a Cents,
+    pub created_on: chrono::naive::NaiveDateTime,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    let mut struct_bldr = StructBuilder::new("customers".into());
+    struct_bldr
+        .set_schema("invoicing".into())
+        .set_type_overrides(overrides)
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Customers {
+    pub customer_id: i32,
+    pub email: crate::BillingEmail,
+    pub invoice_email: Option<crate::Email>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+
+    assert!("no equals sign".parse::<TypeOverrides>().is_err());
+}
+
+#[test]
+#[ignore]
+fn test_domain() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS invoices;
+DROP DOMAIN IF EXISTS invoice_amount;
+DROP DOMAIN IF EXISTS invoice_code;
+CREATE DOMAIN invoice_amount AS bigint CHECK (VALUE >= 0);
+CREATE DOMAIN invoice_code AS TEXT CHECK (length(VALUE) = 8);
+CREATE TABLE invoices (
+        invoice_id serial PRIMARY KEY,
+        amount invoice_amount NOT NULL,
+        code invoice_code,
+        line_amounts invoice_amount[]
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "invoices").unwrap();
+    assert_eq!(
+        struct_bldr.columns["amount"].domain.as_deref(),
+        Some("invoice_amount")
+    );
+    assert_eq!(struct_bldr.build_user_types().unwrap(), "");
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Invoices {
+    pub invoice_id: i32,
+    pub amount: i64,
+    pub code: Option<String>,
+    pub line_amounts: Option<Vec<i64>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("VALUES($1::int8, $2::text, $3::int8[])"));
+    assert!(methods.contains(
+        r#"pub const COLUMNS: &'static str = "invoice_id, amount, code, line_amounts::int8[] AS line_amounts";"#
+    ));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
     tmpdir.close().unwrap();
     ret.unwrap();
+
+    let mut struct_bldr = StructBuilder::new_from_conn(client, "invoices").unwrap();
+    struct_bldr.set_domain_mode(DomainMode::Newtype);
+    assert_eq!(
+        struct_bldr
+            .build_user_types()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"#[derive(Debug, Clone, Copy, PartialEq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "invoice_amount")]
+pub struct InvoiceAmount(pub i64);
+#[derive(Debug, Clone, PartialEq, postgres_types::ToSql, postgres_types::FromSql)]
+#[postgres(name = "invoice_code")]
+pub struct InvoiceCode(pub String);"#
+            .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct InvoicesNew<'a> {
+    pub amount: InvoiceAmount,
+    pub code: Option<&'a InvoiceCode>,
+    pub line_amounts: Option<&'a [InvoiceAmount]>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("VALUES($1, $2, $3)"));
+    assert!(methods.contains(
+        r#"pub const COLUMNS: &'static str = "invoice_id, amount, code, line_amounts";"#
+    ));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+fn test_foreign_keys() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS shipments;
+DROP TABLE IF EXISTS warehouse_bins;
+DROP TABLE IF EXISTS warehouses;
+CREATE TABLE warehouses (
+        warehouse_id serial PRIMARY KEY,
+        name TEXT NOT NULL
+);
+CREATE TABLE warehouse_bins (
+        warehouse_id integer NOT NULL REFERENCES warehouses,
+        bin_code TEXT NOT NULL,
+        PRIMARY KEY (warehouse_id, bin_code)
+);
+CREATE TABLE shipments (
+        shipment_id serial PRIMARY KEY,
+        origin_id integer NOT NULL REFERENCES warehouses (warehouse_id),
+        bin_warehouse_id integer,
+        bin_code TEXT,
+        FOREIGN KEY (bin_code, bin_warehouse_id) REFERENCES warehouse_bins (bin_code, warehouse_id)
+);"#,
+        )
+        .unwrap();
+
+    // Being referenced by other tables does not affect the referenced table.
+    let struct_bldr = StructBuilder::new_from_conn(client, "warehouses").unwrap();
+    assert_eq!(
+        struct_bldr.constraints,
+        vec![Constraint::PrimaryKey {
+            name: "warehouses_pkey".into(),
+            columns: vec!["warehouse_id".into()],
+        }]
+    );
+    assert!(struct_bldr.columns["warehouse_id"].foreign_key.is_none());
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "shipments").unwrap();
+    assert_eq!(
+        struct_bldr.columns["origin_id"].foreign_key,
+        Some(ForeignKey::new(
+            "warehouses".into(),
+            vec!["warehouse_id".into()]
+        ))
+    );
+    assert_eq!(
+        struct_bldr.columns["bin_warehouse_id"].foreign_key,
+        Some(ForeignKey::new(
+            "warehouse_bins".into(),
+            vec!["bin_code".into(), "warehouse_id".into()]
+        ))
+    );
+    assert!(struct_bldr.columns["shipment_id"].foreign_key.is_none());
+    assert_eq!(
+        struct_bldr.constraints,
+        vec![
+            Constraint::PrimaryKey {
+                name: "shipments_pkey".into(),
+                columns: vec!["shipment_id".into()],
+            },
+            Constraint::ForeignKey {
+                name: "shipments_bin_code_bin_warehouse_id_fkey".into(),
+                columns: vec!["bin_code".into(), "bin_warehouse_id".into()].into(),
+                ref_schema: "public".into(),
+                ref_table: "warehouse_bins".into(),
+                ref_columns: vec!["bin_code".into(), "warehouse_id".into()].into(),
+            },
+            Constraint::ForeignKey {
+                name: "shipments_origin_id_fkey".into(),
+                columns: vec!["origin_id".into()].into(),
+                ref_schema: "public".into(),
+                ref_table: "warehouses".into(),
+                ref_columns: vec!["warehouse_id".into()].into(),
+            },
+        ]
+    );
+
+    // Foreign key names are only unique per table, and may reference another schema.
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS fk_test CASCADE;
+DROP SCHEMA IF EXISTS fk_other CASCADE;
+CREATE SCHEMA fk_test;
+CREATE SCHEMA fk_other;
+CREATE TABLE fk_other.owners (
+        owner_id serial PRIMARY KEY
+);
+CREATE TABLE fk_test.parents (
+        id serial PRIMARY KEY
+);
+CREATE TABLE fk_test.a (
+        id serial PRIMARY KEY,
+        parent_id integer NOT NULL,
+        owner_id integer REFERENCES fk_other.owners,
+        CONSTRAINT fk_parent FOREIGN KEY (parent_id) REFERENCES fk_test.parents
+);
+CREATE TABLE fk_test.b (
+        id serial PRIMARY KEY,
+        parent_id integer NOT NULL,
+        CONSTRAINT fk_parent FOREIGN KEY (parent_id) REFERENCES fk_test.parents
+);"#,
+        )
+        .unwrap();
+    let mut information_schema = StructBuilder::new("a".into());
+    information_schema
+        .set_schema("fk_test".into())
+        .introspect(client)
+        .unwrap();
+    let mut catalog = StructBuilder::new("a".into());
+    catalog
+        .set_schema("fk_test".into())
+        .introspect_catalog(client)
+        .unwrap();
+    assert_eq!(
+        information_schema.columns["parent_id"].foreign_key,
+        Some(ForeignKey::new("parents".into(), vec!["id".into()]).set_to_schema("fk_test".into()))
+    );
+    assert_eq!(
+        information_schema.columns["owner_id"].foreign_key,
+        Some(
+            ForeignKey::new("owners".into(), vec!["owner_id".into()])
+                .set_to_schema("fk_other".into())
+        )
+    );
+    assert_eq!(
+        information_schema.constraints,
+        vec![
+            Constraint::PrimaryKey {
+                name: "a_pkey".into(),
+                columns: vec!["id".into()],
+            },
+            Constraint::ForeignKey {
+                name: "a_owner_id_fkey".into(),
+                columns: vec!["owner_id".into()].into(),
+                ref_schema: "fk_other".into(),
+                ref_table: "owners".into(),
+                ref_columns: vec!["owner_id".into()].into(),
+            },
+            Constraint::ForeignKey {
+                name: "fk_parent".into(),
+                columns: vec!["parent_id".into()].into(),
+                ref_schema: "fk_test".into(),
+                ref_table: "parents".into(),
+                ref_columns: vec!["id".into()].into(),
+            },
+        ]
+    );
+    assert_eq!(catalog.constraints, information_schema.constraints);
+    for (name, col) in &catalog.columns {
+        assert_eq!(
+            col.foreign_key,
+            information_schema.columns[name].foreign_key
+        );
+    }
+
+    let mut database = DatabaseBuilder::new();
+    database
+        .set_schemas(vec!["fk_test".into()])
+        .set_backend(Backend::Diesel)
+        .introspect(client)
+        .unwrap();
+    let schema = &database.build_modules().unwrap()["schema.rs"];
+    assert!(schema.contains("diesel::joinable!(a -> parents (parent_id));"));
+    assert!(schema.contains("diesel::joinable!(b -> parents (parent_id));"));
+    assert!(!schema.contains("owners"));
+}
+
+#[test]
+#[ignore]
+fn test_check_constraints() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS products;
+CREATE TABLE products (
+        product_id serial PRIMARY KEY CHECK (product_id > 0),
+        name varchar(40) NOT NULL CHECK (char_length(name) >= 2),
+        sku TEXT CHECK (length(sku) <= 12),
+        status TEXT NOT NULL CHECK (status IN ('draft', 'live')),
+        quantity smallint NOT NULL CHECK (quantity BETWEEN 0 AND 1000),
+        weight double precision CHECK (weight > 0),
+        price_cents bigint NOT NULL,
+        sale_price_cents bigint,
+        stage TEXT NOT NULL DEFAULT 'new' CHECK (char_length(stage) <= 10),
+        batch integer DEFAULT 1 CHECK (batch > 0),
+        nan double precision NOT NULL,
+        CHECK (sale_price_cents < price_cents),
+        CONSTRAINT products_nan_check CHECK (weight < nan)
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "products").unwrap();
+    assert_eq!(
+        struct_bldr.constraints,
+        vec![
+            Constraint::PrimaryKey {
+                name: "products_pkey".into(),
+                columns: vec!["product_id".into()],
+            },
+            Constraint::Check {
+                name: "products_batch_check".into(),
+                expression: "batch > 0".into(),
+            },
+            Constraint::Check {
+                name: "products_check".into(),
+                expression: "sale_price_cents < price_cents".into(),
+            },
+            Constraint::Check {
+                name: "products_name_check".into(),
+                expression: "char_length((name)::text) >= 2".into(),
+            },
+            Constraint::Check {
+                name: "products_nan_check".into(),
+                expression: "weight < nan".into(),
+            },
+            Constraint::Check {
+                name: "products_product_id_check".into(),
+                expression: "product_id > 0".into(),
+            },
+            Constraint::Check {
+                name: "products_quantity_check".into(),
+                expression: "(quantity >= 0) AND (quantity <= 1000)".into(),
+            },
+            Constraint::Check {
+                name: "products_sku_check".into(),
+                expression: "length(sku) <= 12".into(),
+            },
+            Constraint::Check {
+                name: "products_stage_check".into(),
+                expression: "char_length(stage) <= 10".into(),
+            },
+            Constraint::Check {
+                name: "products_status_check".into(),
+                expression: "status = ANY (ARRAY['draft'::text, 'live'::text])".into(),
+            },
+            Constraint::Check {
+                name: "products_weight_check".into(),
+                expression: "weight > (0)::double precision".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct ProductsNew<'a> {
+    pub name: &'a str,
+    pub sku: Option<&'a str>,
+    pub status: &'a str,
+    pub quantity: i16,
+    pub weight: Option<f64>,
+    pub price_cents: i64,
+    pub sale_price_cents: Option<i64>,
+    pub stage: Option<&'a str>,
+    pub batch: Option<Option<i32>>,
+    pub nan: f64,
+}
+impl ProductsNew<'_> {
+    /// Check the fields against the table's CHECK constraints, returning the name of the
+    /// first one that fails.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if let Some(Some(value)) = self.batch {
+            if value <= 0 {
+                return Err("products_batch_check");
+            }
+        }
+        if self.name.chars().count() < 2 {
+            return Err("products_name_check");
+        }
+        if self.quantity < 0 {
+            return Err("products_quantity_check");
+        }
+        if self.quantity > 1000 {
+            return Err("products_quantity_check");
+        }
+        if let Some(value) = self.sku {
+            if value.chars().count() > 12 {
+                return Err("products_sku_check");
+            }
+        }
+        if let Some(value) = self.stage {
+            if value.chars().count() > 10 {
+                return Err("products_stage_check");
+            }
+        }
+        if !["draft", "live"].contains(&self.status) {
+            return Err("products_status_check");
+        }
+        if let Some(value) = self.weight {
+            if value <= 0.0 {
+                return Err("products_weight_check");
+            }
+        }
+        Ok(())
+    }
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_defaults() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS sessions;
+CREATE TABLE sessions (
+        session_id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+        seq integer GENERATED BY DEFAULT AS IDENTITY,
+        token TEXT NOT NULL,
+        created_on TIMESTAMP NOT NULL DEFAULT now(),
+        note TEXT DEFAULT 'none',
+        token_length integer GENERATED ALWAYS AS (length(token)) STORED
+);"#,
+        )
+        .unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "sessions").unwrap();
+    assert_eq!(
+        struct_bldr.columns["session_id"].identity,
+        Some(Identity::Always)
+    );
+    assert_eq!(
+        struct_bldr.columns["seq"].identity,
+        Some(Identity::ByDefault)
+    );
+    assert_eq!(
+        struct_bldr.columns["created_on"].default.as_deref(),
+        Some("now()")
+    );
+    assert!(struct_bldr.columns["token_length"].generated);
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Sessions {
+    pub session_id: i64,
+    pub seq: i32,
+    pub token: String,
+    pub created_on: chrono::naive::NaiveDateTime,
+    pub note: Option<String>,
+    pub token_length: Option<i32>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert_eq!(
+        struct_bldr
+            .build_new_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct SessionsNew<'a> {
+    pub seq: Option<i32>,
+    pub token: &'a str,
+    pub created_on: Option<chrono::naive::NaiveDateTime>,
+    pub note: Option<Option<&'a str>>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert!(struct_bldr
+        .build_type_methods()
+        .unwrap()
+        .contains(r#"None => values.push("DEFAULT".to_string()),"#));
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_schema() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"CREATE SCHEMA IF NOT EXISTS billing;
+DROP TABLE IF EXISTS billing."Order";
+DROP TABLE IF EXISTS public."Order";
+CREATE TABLE billing."Order" (
+        order_id serial PRIMARY KEY,
+        "user" TEXT NOT NULL,
+        "Total" bigint NOT NULL
+);
+CREATE TABLE public."Order" (
+        order_id serial PRIMARY KEY,
+        placed_on TIMESTAMP NOT NULL
+);"#,
+        )
+        .unwrap();
+
+    let mut struct_bldr = StructBuilder::new("Order".into());
+    struct_bldr
+        .set_schema("billing".into())
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"pub struct Order {
+    pub order_id: i32,
+    pub user: String,
+    pub total: i64,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains(
+        r#"client.prepare("INSERT INTO billing.\"Order\"(\"user\", \"Total\") VALUES($1, $2);")"#
+    ));
+    assert!(
+        methods.contains(r#"pub const COLUMNS: &'static str = "order_id, \"user\", \"Total\"";"#)
+    );
+    assert!(methods.contains(r#"total: row.try_get("Total")?,"#));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+
+    let struct_bldr = StructBuilder::new_from_conn(client, "Order").unwrap();
+    assert_eq!(struct_bldr.schema, "public");
+    assert_eq!(
+        struct_bldr.columns.keys().collect::<Vec<_>>(),
+        ["order_id", "placed_on"]
+    );
+}
+
+#[test]
+fn test_catalog_introspection() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS catalog_test CASCADE;
+CREATE SCHEMA catalog_test;
+SET search_path TO catalog_test;
+CREATE TYPE item_state AS ENUM ('new', 'sold');
+CREATE TYPE dimensions AS (width double precision, height double precision, tags TEXT[]);
+CREATE DOMAIN weight_grams AS integer NOT NULL CHECK (VALUE > 0);
+CREATE TABLE makers (
+        maker_id integer,
+        region TEXT,
+        PRIMARY KEY (maker_id, region)
+);
+CREATE TABLE items (
+        item_id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+        code varchar(255) NOT NULL UNIQUE,
+        price numeric(10,2) NOT NULL CHECK (price > 0),
+        state item_state NOT NULL DEFAULT 'new',
+        size dimensions,
+        weight weight_grams,
+        weights weight_grams[],
+        attributes jsonb,
+        maker_id integer,
+        maker_region TEXT,
+        listed_on TIMESTAMP WITH TIME ZONE DEFAULT now(),
+        code_length integer GENERATED ALWAYS AS (length(code)) STORED,
+        FOREIGN KEY (maker_id, maker_region) REFERENCES makers
+);
+COMMENT ON COLUMN items.price IS 'Price in euros';
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    for domain_mode in [DomainMode::BaseType, DomainMode::Newtype] {
+        let mut information_schema = StructBuilder::new("items".into());
+        information_schema
+            .set_schema("catalog_test".into())
+            .set_domain_mode(domain_mode)
+            .introspect(client)
+            .unwrap();
+        let mut catalog = StructBuilder::new("items".into());
+        catalog
+            .set_schema("catalog_test".into())
+            .set_domain_mode(domain_mode)
+            .introspect_catalog(client)
+            .unwrap();
+
+        let price = &catalog.columns["price"];
+        assert_eq!(price.type_def.as_deref(), Some("numeric(10,2)"));
+        assert_eq!(price.comment.as_deref(), Some("Price in euros"));
+        assert_eq!(
+            catalog.columns["code"].type_def.as_deref(),
+            Some("character varying(255)")
+        );
+        assert!(catalog
+            .build_type()
+            .unwrap()
+            .contains("/// Price in euros\n    pub price: rust_decimal::Decimal,"));
+
+        // Apart from the details only `pg_catalog` has, both produce the same model.
+        for col in catalog.columns.values_mut() {
+            col.type_def = None;
+            col.comment = None;
+        }
+        assert_eq!(catalog, information_schema);
+        assert_eq!(
+            catalog.build_user_types().unwrap(),
+            information_schema.build_user_types().unwrap()
+        );
+        assert_eq!(
+            catalog.build_type().unwrap(),
+            information_schema.build_type().unwrap()
+        );
+        assert_eq!(
+            catalog.build_new_type().unwrap(),
+            information_schema.build_new_type().unwrap()
+        );
+        assert_eq!(
+            catalog.build_type_methods().unwrap(),
+            information_schema.build_type_methods().unwrap()
+        );
+    }
+}
+
+#[test]
+#[ignore]
+fn test_database() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS library CASCADE;
+DROP SCHEMA IF EXISTS library_archive CASCADE;
+CREATE SCHEMA library;
+CREATE SCHEMA library_archive;
+CREATE TYPE library.loan_state AS ENUM ('open', 'returned');
+CREATE TYPE library.address AS (street TEXT, city TEXT);
+CREATE TABLE library.members (
+        member_id serial PRIMARY KEY,
+        home library.address NOT NULL
+);
+CREATE TABLE library.loans (
+        loan_id serial PRIMARY KEY,
+        member_id integer NOT NULL REFERENCES library.members,
+        state library.loan_state NOT NULL,
+        ship_to library.address
+);
+CREATE TABLE library.books (
+        book_id serial PRIMARY KEY,
+        title TEXT NOT NULL
+);
+CREATE TABLE library.books_audit (
+        title TEXT NOT NULL
+);
+CREATE TABLE library_archive.loans (
+        loan_id integer PRIMARY KEY,
+        state library.loan_state NOT NULL
+);"#,
+        )
+        .unwrap();
+
+    let mut database = DatabaseBuilder::new();
+    database
+        .set_schemas(vec!["library".into(), "library_archive".into()])
+        .add_exclude("*_audit".into());
+    assert!(database.is_selected("library", "books"));
+    assert!(!database.is_selected("library", "books_audit"));
+    database
+        .add_include("library.*".into())
+        .add_include("*loans".into());
+    assert!(database.is_selected("library", "books"));
+    assert!(database.is_selected("library_archive", "loans"));
+    assert!(!database.is_selected("library_archive", "members"));
+    database.introspect(client).unwrap();
+
+    let modules = database.build_modules().unwrap();
+    assert_eq!(
+        modules.keys().collect::<Vec<_>>(),
+        [
+            "mod.rs",
+            "books.rs",
+            "library_loans.rs",
+            "members.rs",
+            "library_archive_loans.rs",
+            "types.rs"
+        ]
+    );
+    assert_eq!(
+        modules["mod.rs"],
+        "pub mod types;\npub mod books;\npub mod library_loans;\npub mod members;\npub mod library_archive_loans;\n"
+    );
+    assert!(!modules["books.rs"].contains("use super::types::*;"));
+    assert!(modules["members.rs"].starts_with("use super::types::*;"));
+    assert!(modules["library_archive_loans.rs"].contains("INSERT INTO library_archive.loans"));
+    // Shared types are only defined once.
+    let types = &modules["types.rs"];
+    assert_eq!(types.matches("pub enum LoanState").count(), 1);
+    assert_eq!(types.matches("pub struct Address").count(), 1);
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = init_cargo_project(tmpdir.path()).and_then(|()| {
+        database.write_modules(&tmpdir.path().join("src/db"))?;
+        std::fs::write(
+            tmpdir.path().join("src/lib.rs"),
+            "#![allow(dead_code)]\npub mod db;\n",
+        )?;
+        check_cargo_project(tmpdir.path())
+    });
+    tmpdir.close().unwrap();
+    ret.unwrap();
+
+    // Same-named types of different schemas would share a Rust name.
+    client
+        .batch_execute(
+            r#"CREATE TYPE library.status AS ENUM ('active', 'banned');
+CREATE TYPE library_archive.status AS ENUM ('archived');
+ALTER TABLE library.books ADD COLUMN status library.status;
+ALTER TABLE library_archive.loans ADD COLUMN status library_archive.status;"#,
+        )
+        .unwrap();
+    let mut database = DatabaseBuilder::new();
+    database
+        .set_schemas(vec!["library".into(), "library_archive".into()])
+        .introspect(client)
+        .unwrap();
+    match database.build_modules() {
+        Err(Error::AmbiguousType {
+            first,
+            second,
+            rust_type,
+        }) => {
+            assert_eq!(first, "library.status");
+            assert_eq!(second, "library_archive.status");
+            assert_eq!(rust_type, "Status");
+        }
+        other => panic!("expected an ambiguous type error, got {other:?}"),
+    }
+}
+
+#[test]
+#[cfg(feature = "tokio-postgres")]
+fn test_async_introspection() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS async_test CASCADE;
+CREATE SCHEMA async_test;
+SET search_path TO async_test;
+CREATE TYPE shelf_state AS ENUM ('empty', 'full');
+CREATE TYPE shelf_place AS (aisle integer, level integer);
+CREATE DOMAIN capacity AS integer CHECK (VALUE >= 0);
+CREATE TABLE warehouses (
+        warehouse_id serial PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE shelves (
+        shelf_id bigint GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+        warehouse_id integer NOT NULL REFERENCES warehouses,
+        state shelf_state NOT NULL DEFAULT 'empty',
+        place shelf_place,
+        capacity capacity NOT NULL,
+        labels TEXT[],
+        width real CHECK (width > 0)
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let async_client = connect_async(&runtime);
+
+    for table in ["warehouses", "shelves"] {
+        let mut sync = StructBuilder::new(table.into());
+        sync.set_schema("async_test".into())
+            .introspect(client)
+            .unwrap();
+        let mut r#async = StructBuilder::new(table.into());
+        runtime
+            .block_on(
+                r#async
+                    .set_schema("async_test".into())
+                    .introspect_async(&async_client),
+            )
+            .unwrap();
+        assert_eq!(r#async, sync);
+
+        let mut sync = StructBuilder::new(table.into());
+        sync.set_schema("async_test".into())
+            .introspect_catalog(client)
+            .unwrap();
+        let mut r#async = StructBuilder::new(table.into());
+        runtime
+            .block_on(
+                r#async
+                    .set_schema("async_test".into())
+                    .introspect_catalog_async(&async_client),
+            )
+            .unwrap();
+        assert_eq!(r#async, sync);
+    }
+
+    let mut sync = DatabaseBuilder::new();
+    sync.set_schemas(vec!["async_test".into()])
+        .introspect(client)
+        .unwrap();
+    let mut r#async = DatabaseBuilder::new();
+    runtime
+        .block_on(
+            r#async
+                .set_schemas(vec!["async_test".into()])
+                .introspect_async(&async_client),
+        )
+        .unwrap();
+    assert_eq!(r#async, sync);
+    assert_eq!(r#async.tables.len(), 2);
+}
+
+#[test]
+#[ignore]
+#[cfg(feature = "tokio-postgres")]
+fn test_tokio_postgres_backend() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS tokio_backend CASCADE;
+CREATE SCHEMA tokio_backend;
+CREATE TABLE tokio_backend.tags (
+        tag_id serial PRIMARY KEY,
+        label TEXT NOT NULL
+);
+CREATE TABLE tokio_backend.events (
+        event_id serial PRIMARY KEY,
+        name TEXT NOT NULL,
+        happened_at TIMESTAMP NOT NULL DEFAULT now()
+);
+CREATE TABLE tokio_backend.tickets (
+        ticket_id serial PRIMARY KEY
+);"#,
+        )
+        .unwrap();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let async_client = connect_async(&runtime);
+    let mut database = DatabaseBuilder::new();
+    database
+        .set_schemas(vec!["tokio_backend".into()])
+        .set_backend(Backend::TokioPostgres);
+    runtime
+        .block_on(database.introspect_catalog_async(&async_client))
+        .unwrap();
+
+    let modules = database.build_modules().unwrap();
+    assert!(modules["tags.rs"]
+        .replace([' ', '\r', '\n'], "")
+        .contains(
+            &r#"pub async fn insert_slice(client: &impl tokio_postgres::GenericClient, slice: &[TagsNew<'_>]) -> Result<(), tokio_postgres::Error> {
+            let statement = client.prepare("INSERT INTO tokio_backend.tags(label) VALUES($1);").await?;"#
+                .replace([' ', '\r', '\n'], "")
+        ));
+    assert!(modules["events.rs"].contains("client.execute(&sql_statement, &params).await?;"));
+    assert!(modules["tickets.rs"].contains("client.execute(&statement, &[]).await?;"));
+    assert!(modules["tags.rs"].replace([' ', '\r', '\n'], "").contains(
+        &r#"impl TryFrom<&tokio_postgres::Row> for Tags {
+    type Error = tokio_postgres::Error;"#
+            .replace([' ', '\r', '\n'], "")
+    ));
+
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = init_cargo_project(tmpdir.path()).and_then(|()| {
+        database.write_modules(&tmpdir.path().join("src/db"))?;
+        std::fs::write(
+            tmpdir.path().join("src/lib.rs"),
+            "#![allow(dead_code)]\npub mod db;\n",
+        )?;
+        check_cargo_project(tmpdir.path())
+    });
+    tmpdir.close().unwrap();
+    ret.unwrap();
+}
+
+#[test]
+#[ignore]
+fn test_sqlx() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS sqlx_test CASCADE;
+CREATE SCHEMA sqlx_test;
+SET search_path TO sqlx_test;
+CREATE TYPE "Mood" AS ENUM ('happy', 'sad');
+CREATE TYPE point2 AS (x double precision, y double precision);
+CREATE DOMAIN positive AS integer CHECK (VALUE > 0);
+CREATE TABLE diary (
+        entry_id serial PRIMARY KEY,
+        "Title" TEXT NOT NULL,
+        mood "Mood" NOT NULL DEFAULT 'happy',
+        moods "Mood"[],
+        place point2,
+        pages positive NOT NULL,
+        written_on DATE NOT NULL,
+        note TEXT DEFAULT 'none'
+);
+CREATE TABLE tags (
+        tag_id serial PRIMARY KEY,
+        label TEXT NOT NULL,
+        weight positive
+);
+CREATE TABLE ticks (
+        tick_id serial PRIMARY KEY
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    for domain_mode in [DomainMode::BaseType, DomainMode::Newtype] {
+        let mut database = DatabaseBuilder::new();
+        database
+            .set_schemas(vec!["sqlx_test".into()])
+            .set_backend(Backend::Sqlx)
+            .set_domain_mode(domain_mode)
+            .introspect(client)
+            .unwrap();
+
+        let modules = database.build_modules().unwrap();
+        assert!(modules["diary.rs"].contains(
+            "#[derive(sqlx::FromRow)]\npub struct Diary {\n    pub entry_id: i32,\n    #[sqlx(rename = \"Title\")]\n    pub title: String,"
+        ));
+        assert!(modules["diary.rs"].contains(
+            "let mut query = sqlx::QueryBuilder::<sqlx::Postgres>::new(\"INSERT INTO sqlx_test.diary(\\\"Title\\\", mood, moods, place, pages, written_on, note) VALUES(\");"
+        ));
+        assert!(modules["tags.rs"].contains(
+            "pub async fn insert_slice(conn: impl sqlx::Acquire<'_, Database = sqlx::Postgres>, slice: &[TagsNew<'_>]) -> Result<(), sqlx::Error>"
+        ));
+        assert!(modules["ticks.rs"]
+            .contains(r#"sqlx::query("INSERT INTO sqlx_test.ticks DEFAULT VALUES;")"#));
+        let types = &modules["types.rs"];
+        assert!(types.contains(
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type)]\n#[sqlx(type_name = \"sqlx_test.\\\"Mood\\\"\")]\npub enum Mood {\n    #[sqlx(rename = \"happy\")]\n    Happy,"
+        ));
+        assert!(types.contains("#[sqlx(type_name = \"sqlx_test.point2\")]\npub struct Point2 {"));
+        assert_eq!(
+            types.contains("#[sqlx(transparent)]\npub struct Positive(pub i32);"),
+            domain_mode == DomainMode::Newtype
+        );
+        assert!(!modules.values().any(|source| source.contains("postgres")));
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ret = init_cargo_project(tmpdir.path()).and_then(|()| {
+            database.write_modules(&tmpdir.path().join("src/db"))?;
+            std::fs::write(
+                tmpdir.path().join("src/lib.rs"),
+                "#![allow(dead_code)]\npub mod db;\n",
+            )?;
+            check_cargo_project(tmpdir.path())
+        });
+        tmpdir.close().unwrap();
+        ret.unwrap();
+    }
+}
+
+#[test]
+#[ignore]
+fn test_diesel() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS diesel_test CASCADE;
+CREATE SCHEMA diesel_test;
+SET search_path TO diesel_test;
+CREATE TYPE "Mood" AS ENUM ('happy', 'so-so');
+CREATE TYPE point2 AS (x double precision, y double precision);
+CREATE DOMAIN positive AS integer CHECK (VALUE > 0);
+CREATE TABLE members (
+        member_id serial PRIMARY KEY,
+        "Name" TEXT NOT NULL,
+        email TEXT NOT NULL,
+        mood "Mood" NOT NULL DEFAULT 'happy',
+        moods "Mood"[],
+        place point2,
+        joined_on DATE NOT NULL,
+        note TEXT DEFAULT 'none'
+);
+COMMENT ON COLUMN members.email IS 'Where to send reminders';
+CREATE TABLE loans (
+        loan_id serial PRIMARY KEY,
+        member_id integer NOT NULL REFERENCES members,
+        pages positive NOT NULL,
+        fee numeric(6, 2)
+);
+CREATE TABLE pings (
+        ping_id serial PRIMARY KEY
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let overrides: TypeOverrides = "members.email = String".parse().unwrap();
+    let mut loans = StructBuilder::new("loans".into());
+    loans
+        .set_schema("diesel_test".into())
+        .set_backend(Backend::Diesel)
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        loans
+            .build_diesel_schema()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"diesel::table! {
+    use diesel::sql_types::*;
+
+    diesel_test.loans (loan_id) {
+        loan_id -> Int4,
+        member_id -> Int4,
+        pages -> Int4,
+        fee -> Nullable<Numeric>,
+    }
+}
+
+diesel::joinable!(loans -> members (member_id));
+diesel::allow_tables_to_appear_in_same_query!(loans, members);"#
+            .replace([' ', '\r', '\n'], "")
+    );
+
+    for domain_mode in [DomainMode::BaseType, DomainMode::Newtype] {
+        let mut database = DatabaseBuilder::new();
+        database
+            .set_schemas(vec!["diesel_test".into()])
+            .set_type_overrides(overrides.clone())
+            .set_backend(Backend::Diesel)
+            .set_domain_mode(domain_mode)
+            .introspect_catalog(client)
+            .unwrap();
+
+        let modules = database.build_modules().unwrap();
+        let schema = &modules["schema.rs"];
+        assert!(schema.contains(
+            "    #[diesel(postgres_type(name = \"Mood\", schema = \"diesel_test\"))]\n    pub struct Mood;"
+        ));
+        assert!(schema.contains(
+            "    use super::sql_types::Mood;\n    use super::sql_types::Point2;\n\n    diesel_test.members (member_id) {\n        member_id -> Int4,\n        #[sql_name = \"Name\"]\n        name -> Text,\n        /// Where to send reminders\n        email -> Text,\n        mood -> Mood,\n        moods -> Nullable<Array<Mood>>,"
+        ));
+        assert!(schema.contains("diesel::joinable!(loans -> members (member_id));"));
+        assert!(schema.contains(
+            "diesel::allow_tables_to_appear_in_same_query!(\n    loans,\n    members,\n    pings,\n);"
+        ));
+        assert!(modules["members.rs"].starts_with(
+            "use super::schema::members;\nuse super::types::*;\n\n#[derive(diesel::Queryable, diesel::Selectable)]\n#[diesel(table_name = members)]"
+        ));
+        assert!(modules["loans.rs"].contains(
+            "pub fn insert_slice(conn: &mut diesel::PgConnection, slice: &[LoansNew]) -> Result<(), diesel::result::Error>"
+        ));
+        assert!(modules["pings.rs"].contains("default_values()"));
+        let types = &modules["types.rs"];
+        assert!(types.starts_with("use super::schema::sql_types;"));
+        assert!(types.contains(
+            "impl diesel::deserialize::FromSql<sql_types::Mood, diesel::pg::Pg> for Mood {"
+        ));
+        assert!(types.contains("b\"so-so\" => Ok(Self::SoSo),"));
+        assert_eq!(
+            types.contains(
+                "#[diesel(sql_type = diesel::sql_types::Int4)]\npub struct Positive(pub i32);"
+            ),
+            domain_mode == DomainMode::Newtype
+        );
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ret = init_cargo_project(tmpdir.path()).and_then(|()| {
+            database.write_modules(&tmpdir.path().join("src/db"))?;
+            std::fs::write(
+                tmpdir.path().join("src/lib.rs"),
+                "#![allow(dead_code)]\npub mod db;\n",
+            )?;
+            check_cargo_project(tmpdir.path())
+        });
+        tmpdir.close().unwrap();
+        ret.unwrap();
+    }
+}
+
+#[test]
+#[ignore]
+fn test_primary_key_methods() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS key_test CASCADE;
+CREATE SCHEMA key_test;
+SET search_path TO key_test;
+CREATE DOMAIN sku AS TEXT CHECK (VALUE <> '');
+CREATE TABLE stock (
+        bin TEXT NOT NULL,
+        "Warehouse" integer NOT NULL,
+        quantity integer NOT NULL,
+        PRIMARY KEY ("Warehouse", bin)
+);
+CREATE TABLE products (
+        code sku PRIMARY KEY,
+        name TEXT NOT NULL
+);
+CREATE TABLE flags (
+        flag_id serial PRIMARY KEY
+);
+CREATE TABLE tickets (
+        code TEXT PRIMARY KEY DEFAULT md5(random()::text),
+        title TEXT NOT NULL
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let mut struct_bldr = StructBuilder::new("stock".into());
+    struct_bldr
+        .set_schema("key_test".into())
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr.constraints,
+        vec![Constraint::PrimaryKey {
+            name: "stock_pkey".into(),
+            columns: vec!["Warehouse".into(), "bin".into()],
+        }]
+    );
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains(
+        "pub fn get(client: &mut postgres::Client, warehouse: i32, bin: &str) -> Result<Option<Self>, postgres::Error>"
+    ));
+    assert!(methods.contains(r#"FROM key_test.stock WHERE \"Warehouse\" = $1 AND bin = $2;"#));
+    assert!(methods.contains(
+        "pub fn get_many(client: &mut postgres::Client, keys: &[(i32, &str)]) -> Result<Vec<Self>, postgres::Error>"
+    ));
+    assert!(methods.contains(
+        r#"UPDATE key_test.stock SET quantity = $3 WHERE \"Warehouse\" = $1 AND bin = $2;"#
+    ));
+
+    for domain_mode in [DomainMode::BaseType, DomainMode::Newtype] {
+        let generated = check_database(client, "key_test", |database| {
+            database.set_domain_mode(domain_mode);
+        });
+        for (backend, modules) in generated {
+            assert_eq!(
+                modules["products.rs"].contains("code = ANY($1::text[]);"),
+                domain_mode == DomainMode::BaseType && backend != Backend::Diesel
+            );
+            assert!(modules["flags.rs"].contains("fn exists("));
+            assert!(!modules["flags.rs"].contains("fn update("));
+            assert!(!modules["flags.rs"].contains("pub flag_id: Option<"));
+            assert!(modules["tickets.rs"].contains("pub code: Option<&'a str>,"));
+            if backend == Backend::Postgres {
+                assert!(modules["tickets.rs"]
+                    .contains(r#"None => values.push("DEFAULT".to_string()),"#));
+            }
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn test_unique_lookups() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS unique_test CASCADE;
+CREATE SCHEMA unique_test;
+SET search_path TO unique_test;
+CREATE TABLE seats (
+        seat_id serial PRIMARY KEY,
+        code TEXT NOT NULL UNIQUE,
+        "Row" integer NOT NULL,
+        number integer NOT NULL,
+        tag TEXT,
+        label TEXT,
+        note TEXT,
+        UNIQUE ("Row", number)
+);
+CREATE UNIQUE INDEX seats_tag_key ON seats (tag) INCLUDE (note);
+CREATE UNIQUE INDEX seats_label_partial ON seats (label) WHERE label <> '';
+CREATE UNIQUE INDEX seats_note_lower ON seats (lower(note));
+CREATE UNIQUE INDEX seats_code_again ON seats (code);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let mut information_schema = StructBuilder::new("seats".into());
+    information_schema
+        .set_schema("unique_test".into())
+        .introspect(client)
+        .unwrap();
+    let mut catalog = StructBuilder::new("seats".into());
+    catalog
+        .set_schema("unique_test".into())
+        .introspect_catalog(client)
+        .unwrap();
+    assert_eq!(
+        catalog.constraints,
+        vec![
+            Constraint::PrimaryKey {
+                name: "seats_pkey".into(),
+                columns: vec!["seat_id".into()],
+            },
+            Constraint::Unique {
+                name: "seats_Row_number_key".into(),
+                columns: vec!["Row".into(), "number".into()],
+            },
+            Constraint::Unique {
+                name: "seats_code_key".into(),
+                columns: vec!["code".into()],
+            },
+            Constraint::Unique {
+                name: "seats_code_again".into(),
+                columns: vec!["code".into()],
+            },
+            Constraint::Unique {
+                name: "seats_tag_key".into(),
+                columns: vec!["tag".into()],
+            },
+        ]
+    );
+    assert_eq!(catalog.constraints, information_schema.constraints);
+    assert!(catalog.columns["tag"].unique);
+    assert!(!catalog.columns["label"].unique);
+
+    let methods = catalog.build_type_methods().unwrap();
+    assert!(methods.contains(
+        "pub fn find_by_code(client: &mut postgres::Client, code: &str) -> Result<Option<Self>, postgres::Error>"
+    ));
+    assert!(methods.contains(r#"FROM unique_test.seats WHERE \"Row\" = $1 AND number = $2;"#));
+    assert!(methods.contains(
+        "pub fn find_by_row_and_number(client: &mut postgres::Client, row: i32, number: i32)"
+    ));
+    assert!(methods.contains("pub fn find_by_tag(client: &mut postgres::Client, tag: &str)"));
+    assert_eq!(methods.matches("fn find_by_").count(), 3);
+
+    check_database(client, "unique_test", |_| {});
+}
+
+#[test]
+#[ignore]
+fn test_upsert() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS upsert_test CASCADE;
+CREATE SCHEMA upsert_test;
+SET search_path TO upsert_test;
+CREATE TABLE members (
+        member_id serial PRIMARY KEY,
+        email TEXT NOT NULL UNIQUE,
+        nick TEXT NOT NULL,
+        score integer NOT NULL DEFAULT 0
+);
+CREATE TABLE tags (
+        owner TEXT NOT NULL,
+        name TEXT NOT NULL,
+        PRIMARY KEY (owner, name)
+);
+CREATE TABLE notes (
+        note_id serial PRIMARY KEY,
+        body TEXT NOT NULL
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let mut struct_bldr = StructBuilder::new("members".into());
+    struct_bldr
+        .set_schema("upsert_test".into())
+        .introspect(client)
+        .unwrap();
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("ON CONFLICT (email) DO UPDATE SET nick = EXCLUDED.nick;"));
+    assert!(methods
+        .contains("pub fn upsert_slice(client: &mut postgres::Client, slice: &[MembersNew<'_>])"));
+    assert!(
+        methods.contains("pub fn upsert(client: &mut postgres::Client, entry: &MembersNew<'_>)")
+    );
+
+    struct_bldr.set_on_conflict(
+        OnConflict::new()
+            .set_target(vec!["email".into()])
+            .set_update(vec!["score".into()])
+            .clone(),
+    );
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("ON CONFLICT (email) DO UPDATE SET score = EXCLUDED.score;"));
+    struct_bldr.set_on_conflict(OnConflict::new().set_do_nothing(true).clone());
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("ON CONFLICT (email) DO NOTHING;"));
+
+    let mut struct_bldr = StructBuilder::new("tags".into());
+    struct_bldr
+        .set_schema("upsert_test".into())
+        .introspect(client)
+        .unwrap();
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains("ON CONFLICT (owner, name) DO NOTHING;"));
+
+    let mut struct_bldr = StructBuilder::new("notes".into());
+    struct_bldr
+        .set_schema("upsert_test".into())
+        .introspect(client)
+        .unwrap();
+    assert!(!struct_bldr
+        .build_type_methods()
+        .unwrap()
+        .contains("fn upsert"));
+
+    let modules = check_database(client, "upsert_test", |database| {
+        database
+            .set_on_conflict(
+                Some("members".into()),
+                OnConflict::new().set_update(vec!["score".into()]).clone(),
+            )
+            .set_on_conflict(
+                Some("upsert_test.members".into()),
+                OnConflict::new().set_update(vec!["nick".into()]).clone(),
+            )
+            .set_on_conflict(
+                Some("public.members".into()),
+                OnConflict::new().set_do_nothing(true).clone(),
+            );
+    });
+    for (backend, modules) in modules {
+        if backend != Backend::Diesel {
+            assert!(modules["members.rs"].contains("DO UPDATE SET nick = EXCLUDED.nick;"));
+        }
+    }
+}
+
+#[test]
+#[ignore]
+fn test_insert_returning() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP SCHEMA IF EXISTS returning_test CASCADE;
+CREATE SCHEMA returning_test;
+SET search_path TO returning_test;
+CREATE TABLE visits (
+        visit_id serial PRIMARY KEY,
+        page TEXT NOT NULL,
+        "visitedAt" timestamp NOT NULL DEFAULT now()
+);
+CREATE TABLE counters (
+        counter_id serial PRIMARY KEY
+);
+SET search_path TO DEFAULT;"#,
+        )
+        .unwrap();
+
+    let mut struct_bldr = StructBuilder::new("visits".into());
+    struct_bldr
+        .set_schema("returning_test".into())
+        .introspect(client)
+        .unwrap();
+    let methods = struct_bldr.build_type_methods().unwrap();
+    assert!(methods.contains(
+        "pub fn insert_slice_returning(client: &mut postgres::Client, slice: &[VisitsNew<'_>]) -> Result<Vec<Self>, postgres::Error>"
+    ));
+    assert!(methods.contains(
+        "pub fn insert_returning(client: &mut postgres::Client, entry: &VisitsNew<'_>) -> Result<Self, postgres::Error>"
+    ));
+    assert!(methods.contains(
+        r#"format!("{}{}) RETURNING visit_id, page, \"visitedAt\";", "INSERT INTO returning_test.visits(page, \"visitedAt\") VALUES(", values.join(", "))"#
+    ));
+
+    for (_, modules) in check_database(client, "returning_test", |_| {}) {
+        assert!(modules["counters.rs"].contains("fn insert_returning("));
+    }
+}
+
+#[test]
+#[ignore]
+fn test_attributes() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS logins;
+DROP TABLE IF EXISTS sessions;
+DROP TYPE IF EXISTS login_method;
+CREATE TYPE login_method AS ENUM ('password', 'sso');
+CREATE TABLE logins (
+        login_id serial PRIMARY KEY,
+        user_name TEXT NOT NULL,
+        password TEXT NOT NULL,
+        method login_method NOT NULL
+);
+CREATE TABLE sessions (
+        session_id serial PRIMARY KEY,
+        password TEXT
+);"#,
+        )
+        .unwrap();
+
+    let mut attributes = StructAttributes::new();
+    attributes
+        .add_derive(None, "Debug".into())
+        .add_derive(None, "serde::Serialize".into())
+        .add_derive(Some("logins".into()), "Clone".into())
+        .add_derive(Some("billing.logins".into()), "Hash".into())
+        .add_attribute(
+            Some("logins".into()),
+            "#[serde(rename_all = \"camelCase\")]".into(),
+        )
+        .add_column_attribute(None, "password".into(), "#[serde(skip)]".into())
+        .add_column_attribute(
+            Some("public.logins".into()),
+            "user_name".into(),
+            "#[serde(rename = \"user\")]".into(),
+        )
+        .add_column_attribute(
+            Some("sessions".into()),
+            "session_id".into(),
+            "#[serde(rename = \"id\")]".into(),
+        );
+    let mut struct_bldr = StructBuilder::new("logins".into());
+    struct_bldr
+        .set_attributes(attributes.clone())
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"#[derive(Debug, serde::Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Logins {
+    pub login_id: i32,
+    #[serde(rename = "user")]
+    pub user_name: String,
+    #[serde(skip)]
+    pub password: String,
+    pub method: LoginMethod,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+    assert!(struct_bldr.build_new_type().unwrap().starts_with(
+        "#[derive(Debug, serde::Serialize, Clone)]\n#[serde(rename_all = \"camelCase\")]\npub struct LoginsNew<'a> {\n    #[serde(rename = \"user\")]\n    pub user_name: &'a str,\n    #[serde(skip)]\n    pub password: &'a str,"
+    ));
+    assert!(struct_bldr.build_user_types().unwrap().starts_with(
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, postgres_types::ToSql, postgres_types::FromSql)]"
+    ));
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ret = create_cargo_project(tmpdir.path(), struct_bldr);
+    tmpdir.close().unwrap();
+    ret.unwrap();
+
+    let mut struct_bldr = StructBuilder::new("sessions".into());
+    struct_bldr
+        .set_attributes(attributes)
+        .set_backend(Backend::Sqlx)
+        .introspect(client)
+        .unwrap();
+    assert_eq!(
+        struct_bldr
+            .build_type()
+            .unwrap()
+            .replace([' ', '\r', '\n'], ""),
+        r#"#[derive(Debug, serde::Serialize, sqlx::FromRow)]
+pub struct Sessions {
+    #[serde(rename = "id")]
+    pub session_id: i32,
+    #[serde(skip)]
+    pub password: Option<String>,
+}"#
+        .replace([' ', '\r', '\n'], "")
+    );
+}
+
+#[test]
+fn test_errors() {
+    let client = &mut connect();
+
+    client
+        .batch_execute(
+            r#"DROP TABLE IF EXISTS reminders;
+CREATE TABLE reminders (
+        reminder_id serial PRIMARY KEY,
+        remind_at time with time zone NOT NULL
+);"#,
+        )
+        .unwrap();
+
+    match StructBuilder::new_from_conn(client, "reminders") {
+        Err(Error::UnsupportedType {
+            table,
+            column,
+            pg_type,
+        }) => {
+            assert_eq!(table, "reminders");
+            assert_eq!(column, "remind_at");
+            assert_eq!(pg_type, "time with time zone");
+        }
+        other => panic!("expected an unsupported type error, got {other:?}"),
+    }
+
+    let mut struct_bldr = StructBuilder::new("accounts".into());
+    struct_bldr.add_column(Column::new(
+        "balance".into(),
+        Type::Builtin {
+            inner: postgres::types::Type::INT4_RANGE_ARRAY,
+        },
+    ));
+    assert!(matches!(
+        struct_bldr.build_type(),
+        Err(Error::UnsupportedType { .. })
+    ));
+    let range_array = Type::Array {
+        inner: Box::new(Type::Builtin {
+            inner: postgres::types::Type::INT4_RANGE_ARRAY,
+        }),
+    };
+    match range_array.rust_type() {
+        Err(Error::UnknownType { pg_type }) => assert_eq!(pg_type, "_int4range"),
+        other => panic!("expected an unknown type error, got {other:?}"),
+    }
+    assert_eq!(range_array.to_string(), "<unsupported type `_int4range`>");
+    assert!("not a type".parse::<Type>().is_err());
+
+    let mut struct_bldr = StructBuilder::new("invoices".into());
+    struct_bldr.add_column(Column::new(
+        "total".into(),
+        Type::Builtin {
+            inner: postgres::types::Type::MONEY,
+        },
+    ));
+    assert!(struct_bldr.build_type().is_ok());
+    struct_bldr.set_backend(Backend::Sqlx);
+    match struct_bldr.build_type() {
+        Err(Error::UnsupportedByBackend {
+            column,
+            pg_type,
+            backend,
+            ..
+        }) => {
+            assert_eq!(column, "total");
+            assert_eq!(pg_type, "money");
+            assert_eq!(backend, "sqlx");
+        }
+        other => panic!("expected an unsupported by backend error, got {other:?}"),
+    }
+    assert!("mysql".parse::<Backend>().is_err());
+
+    let mut struct_bldr = StructBuilder::new("members".into());
+    struct_bldr
+        .add_column(
+            Column::new(
+                "email".into(),
+                Type::Builtin {
+                    inner: postgres::types::Type::TEXT,
+                },
+            )
+            .set_primary_key(true),
+        )
+        .add_column(Column::new(
+            "nick".into(),
+            Type::Builtin {
+                inner: postgres::types::Type::TEXT,
+            },
+        ))
+        .set_on_conflict(OnConflict::new().set_target(vec!["nick".into()]).clone());
+    match struct_bldr.build_type_methods() {
+        Err(Error::InvalidConflictTarget { table, columns }) => {
+            assert_eq!(table, "members");
+            assert_eq!(columns, "nick");
+        }
+        other => panic!("expected an invalid conflict target error, got {other:?}"),
+    }
+    struct_bldr.set_on_conflict(OnConflict::new().set_update(vec!["email".into()]).clone());
+    match struct_bldr.build_type_methods() {
+        Err(Error::InvalidConflictUpdate { column, .. }) => assert_eq!(column, "email"),
+        other => panic!("expected an invalid conflict update error, got {other:?}"),
+    }
+
+    let mut struct_bldr = StructBuilder::new("events".into());
+    struct_bldr
+        .add_column(Column::new(
+            "name".into(),
+            Type::Builtin {
+                inner: postgres::types::Type::TEXT,
+            },
+        ))
+        .set_backend(Backend::Diesel);
+    assert!(struct_bldr.build_type().is_ok());
+    assert!(matches!(
+        struct_bldr.build_diesel_schema(),
+        Err(Error::MissingPrimaryKey { .. })
+    ));
 }